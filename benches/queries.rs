@@ -0,0 +1,123 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use kd_tree_rs::point::Point;
+use kd_tree_rs::{KdNode, Neighbor, SearchScratch};
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+/// Random points in a square with sides of length `side`.
+fn random_points(n: usize, side: f64, seed: u64) -> Vec<Point<f64>> {
+    let mut seed: u64 = seed;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64 * side
+    };
+    (0..n)
+        .map(|_| Point {
+            x: next(),
+            y: next(),
+        })
+        .collect()
+}
+
+fn radius_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("radius_query");
+    for n in SIZES {
+        // The square grows with the tree so every size has the same density of points.
+        let side: f64 = (n as f64).sqrt();
+        let node: KdNode<f64> = KdNode::build(random_points(n, side, 1));
+        let origins: Vec<Point<f64>> = random_points(1_000, side, 2);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &node, |b, node| {
+            let mut out: Vec<Neighbor<Point<f64>>> = Vec::new();
+            let mut scratch = SearchScratch::new();
+            let mut origin = origins.iter().cycle();
+            b.iter(|| {
+                node.nearest_neighbor_into(*origin.next().unwrap(), 2., &mut out, &mut scratch);
+                black_box(out.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn n_nearest_neighbor(c: &mut Criterion) {
+    let mut group = c.benchmark_group("n_nearest_neighbor");
+    for n in SIZES {
+        // The square grows with the tree so every size has the same density of points.
+        let side: f64 = (n as f64).sqrt();
+        let node: KdNode<f64> = KdNode::build(random_points(n, side, 1));
+        let origins: Vec<Point<f64>> = random_points(1_000, side, 2);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &node, |b, node| {
+            let mut out: Vec<Neighbor<Point<f64>>> = Vec::new();
+            let mut scratch = SearchScratch::new();
+            let mut origin = origins.iter().cycle();
+            b.iter(|| {
+                node.n_nearest_neighbor_into(*origin.next().unwrap(), 8, &mut out, &mut scratch);
+                black_box(out.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn farthest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("farthest");
+    for n in SIZES {
+        let side: f64 = (n as f64).sqrt();
+        let node: KdNode<f64> = KdNode::build(random_points(n, side, 1));
+        let origins: Vec<Point<f64>> = random_points(1_000, side, 2);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &node, |b, node| {
+            let mut origin = origins.iter().cycle();
+            b.iter(|| node.farthest(*origin.next().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn neighbor_pairs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbor_pairs");
+    group.sample_size(10);
+    for n in [10_000, 100_000] {
+        let node: KdNode<f64> = KdNode::build(random_points(n, (n as f64).sqrt(), 1));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &node, |b, node| {
+            b.iter(|| node.neighbor_pairs(2.))
+        });
+    }
+    group.finish();
+}
+
+fn knn_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("knn_graph");
+    group.sample_size(10);
+    for n in [10_000, 100_000] {
+        let node: KdNode<f64> = KdNode::build(random_points(n, (n as f64).sqrt(), 1));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &node, |b, node| {
+            b.iter(|| node.knn_graph(8))
+        });
+    }
+    group.finish();
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    for n in SIZES {
+        let points: Vec<Point<f64>> = random_points(n, (n as f64).sqrt(), 1);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &points, |b, points| {
+            b.iter(|| KdNode::build(points.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    radius_query,
+    n_nearest_neighbor,
+    farthest,
+    neighbor_pairs,
+    knn_graph,
+    build
+);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+//! Bounding boxes of the subtrees of a tree.
+
+use crate::point::KdPoint;
+
+/// The smallest axis aligned box holding every point of a subtree.
+///
+/// Trees built with [`build_with_bounds`](../enum.KdNode.html#method.build_with_bounds), or grown
+/// from empty by inserting, store one in every node. Queries then skip subtrees whose box is out
+/// of reach of the origin instead of only looking at the coordinate they were split on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bounds {
+    /// The lower bound of every axis followed by the upper bound of every axis
+    corners: Box<[f64]>,
+}
+
+impl Bounds {
+    /// The box around the given points, which must not be empty
+    pub(crate) fn of<'a, P: KdPoint + 'a>(points: impl IntoIterator<Item = &'a P>) -> Self {
+        let mut corners: Vec<f64> = vec![f64::INFINITY; 2 * P::DIMENSIONS];
+        corners[P::DIMENSIONS..].fill(f64::NEG_INFINITY);
+        let mut bounds: Bounds = Bounds {
+            corners: corners.into_boxed_slice(),
+        };
+        for point in points {
+            bounds.extend(point);
+        }
+        bounds
+    }
+
+    /// Grow the box to hold the point
+    pub(crate) fn extend<P: KdPoint>(&mut self, point: &P) {
+        let (lower, upper) = self.corners.split_at_mut(P::DIMENSIONS);
+        for dim in 0..P::DIMENSIONS {
+            let value: f64 = point.get(dim).into();
+            lower[dim] = lower[dim].min(value);
+            upper[dim] = upper[dim].max(value);
+        }
+    }
+
+    /// Grow the box to hold another box
+    pub(crate) fn merge(&mut self, other: &Bounds) {
+        let dims: usize = self.corners.len() / 2;
+        for dim in 0..dims {
+            self.corners[dim] = self.corners[dim].min(other.corners[dim]);
+            self.corners[dims + dim] = self.corners[dims + dim].max(other.corners[dims + dim]);
+        }
+    }
+
+    /// The smallest coordinate along every axis
+    pub fn lower(&self) -> &[f64] {
+        &self.corners[..self.corners.len() / 2]
+    }
+
+    /// The largest coordinate along every axis
+    pub fn upper(&self) -> &[f64] {
+        &self.corners[self.corners.len() / 2..]
+    }
+}
@@ -0,0 +1,465 @@
+//! The local shape of point clouds and aligning them with each other.
+
+use crate::flat::Flat;
+use crate::neighbor::{Neighbor, Neighbors};
+use crate::point::{Indexed, KdPoint};
+use crate::search::SearchScratch;
+use crate::{KdNode, KDT};
+
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// The spread of the points around a point, found by [`local_geometry`](fn.local_geometry.html).
+///
+/// Vectors have one coordinate for every dimension of the points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    /// The mean of the neighboring points
+    pub centroid: Vec<f64>,
+    /// The covariance of the neighboring points, one row after the other
+    pub covariance: Vec<f64>,
+    /// The eigenvalues of the covariance from smallest to largest
+    pub eigenvalues: Vec<f64>,
+    /// The unit eigenvector of the smallest eigenvalue, across the surface the points lie on
+    ///
+    /// Which way it points along that line is arbitrary.
+    pub normal: Vec<f64>,
+    /// The smallest eigenvalue over the sum of the eigenvalues, zero on a flat surface
+    pub curvature: f64,
+    /// The number of neighboring points, the point itself included
+    pub neighbors: usize,
+}
+
+/// Estimate the surface normal and local shape around every point of a tree
+///
+/// The neighbors of a point are its nearest points of the tree, itself included, or every
+/// point within a distance of it. The covariance of the neighbors is split into its
+/// eigenvalues, the direction they vary the least in is the normal of the surface through them.
+/// The neighbors of all the points are searched for together as in
+/// [`knn_graph`](../enum.KdNode.html#method.knn_graph).
+///
+/// As with [`dbscan`](../cluster/fn.dbscan.html) the tree is expected to come from
+/// [`build_indexed`](../enum.KdNode.html#method.build_indexed), the geometry of the point at
+/// index `i` is at index `i` of the result.
+///
+/// ```rust
+/// use kd_tree_rs::cloud::local_geometry;
+/// use kd_tree_rs::neighbor::Neighbors;
+/// use kd_tree_rs::{KdNode, KdPoint};
+///
+/// #[derive(Debug, Copy, Clone, PartialEq)]
+/// struct Position([f64; 3]);
+///
+/// impl KdPoint for Position {
+///     type Scalar = f64;
+///     const DIMENSIONS: usize = 3;
+///
+///     fn get(&self, dim: usize) -> f64 {
+///         self.0[dim]
+///     }
+/// }
+///
+/// // Points on the plane z = 0.
+/// let points: Vec<Position> = (0..25).map(|i| Position([(i % 5) as f64, (i / 5) as f64, 0.])).collect();
+/// let geometry = local_geometry(&KdNode::build_indexed(points), Neighbors::Nearest(6));
+/// assert_eq!(geometry[12].normal[2].abs(), 1.);
+/// assert_eq!(geometry[12].curvature, 0.);
+/// ```
+pub fn local_geometry<T: KDT, P: KdPoint<Scalar = T>>(
+    tree: &KdNode<T, Indexed<P>>,
+    neighbors: Neighbors,
+) -> Vec<Geometry> {
+    let flat: Flat<Indexed<P>> = Flat::new(tree);
+    let around: Vec<Vec<usize>> = match neighbors {
+        Neighbors::Nearest(k) => {
+            let nearest = flat.nearest_in(&flat, k, false);
+            nearest
+                .into_iter()
+                .map(|near| near.into_iter().map(|(j, _)| j).collect())
+                .collect()
+        }
+        Neighbors::Within(radius) => {
+            let mut around: Vec<Vec<usize>> = (0..flat.len()).map(|i| vec![i]).collect();
+            for (i, j, _) in flat.pairs_within(radius) {
+                around[i].push(j);
+                around[j].push(i);
+            }
+            around
+        }
+    };
+
+    let mut geometry: Vec<Option<Geometry>> = vec![None; flat.len()];
+    for (point, near) in flat.points.iter().zip(&around) {
+        assert!(
+            point.data < geometry.len(),
+            "The tree must hold the indices from zero up to its length"
+        );
+        geometry[point.data] = Some(shape(near.iter().map(|j| &flat.points[*j])));
+    }
+    geometry
+        .into_iter()
+        .map(|geometry| geometry.expect("Every index must be in the tree once"))
+        .collect()
+}
+
+/// The geometry of a set of points
+fn shape<'a, P: KdPoint + 'a>(points: impl Iterator<Item = &'a P> + Clone) -> Geometry {
+    let dims: usize = P::DIMENSIONS;
+    let mut centroid: Vec<f64> = vec![0.; dims];
+    let mut count: usize = 0;
+    for point in points.clone() {
+        for (dim, sum) in centroid.iter_mut().enumerate() {
+            *sum += point.get(dim).into();
+        }
+        count += 1;
+    }
+    for sum in centroid.iter_mut() {
+        *sum /= count as f64;
+    }
+
+    let mut covariance: Vec<f64> = vec![0.; dims * dims];
+    for point in points {
+        let offset: Vec<f64> = (0..dims)
+            .map(|dim| point.get(dim).into() - centroid[dim])
+            .collect();
+        for row in 0..dims {
+            for column in 0..dims {
+                covariance[row * dims + column] += offset[row] * offset[column] / count as f64;
+            }
+        }
+    }
+
+    let (eigenvalues, mut vectors) = eigen(covariance.clone(), dims);
+    let total: f64 = eigenvalues.iter().sum();
+    Geometry {
+        centroid,
+        covariance,
+        curvature: if total > 0. {
+            eigenvalues[0] / total
+        } else {
+            0.
+        },
+        eigenvalues,
+        normal: vectors.swap_remove(0),
+        neighbors: count,
+    }
+}
+
+/// A rotation followed by a translation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    /// The rotation matrix, one row after the other
+    pub rotation: Vec<f64>,
+    /// The translation applied after the rotation
+    pub translation: Vec<f64>,
+}
+
+impl Transform {
+    /// The transform that leaves points where they are
+    pub fn identity(dims: usize) -> Self {
+        Transform {
+            rotation: (0..dims * dims)
+                .map(|i| if i / dims == i % dims { 1. } else { 0. })
+                .collect(),
+            translation: vec![0.; dims],
+        }
+    }
+
+    /// Move the point with the given coordinates
+    pub fn apply(&self, point: &[f64]) -> Vec<f64> {
+        let dims: usize = self.translation.len();
+        (0..dims)
+            .map(|row| {
+                (0..dims)
+                    .map(|column| self.rotation[row * dims + column] * point[column])
+                    .sum::<f64>()
+                    + self.translation[row]
+            })
+            .collect()
+    }
+
+    /// The transform applying `self` and then `next`
+    fn then(&self, next: &Transform) -> Transform {
+        let dims: usize = self.translation.len();
+        let rotation: Vec<f64> = (0..dims * dims)
+            .map(|i| {
+                (0..dims)
+                    .map(|k| {
+                        next.rotation[i / dims * dims + k] * self.rotation[k * dims + i % dims]
+                    })
+                    .sum()
+            })
+            .collect();
+        Transform {
+            rotation,
+            translation: next.apply(&self.translation),
+        }
+    }
+}
+
+/// The settings of [`icp`](fn.icp.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Icp {
+    /// The most times the transform is improved
+    pub max_iterations: usize,
+    /// Pairs of points further apart than this are not used to improve the transform
+    pub max_distance: f64,
+    /// Stop once an iteration moves no point of the source further than this
+    pub tolerance: f64,
+}
+
+impl Default for Icp {
+    fn default() -> Self {
+        Icp {
+            max_iterations: 50,
+            max_distance: f64::INFINITY,
+            tolerance: 1e-9,
+        }
+    }
+}
+
+/// The result of [`icp`](fn.icp.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registration {
+    /// The transform moving the source onto the target
+    pub transform: Transform,
+    /// The root mean square distance from the moved source points to their nearest target points
+    ///
+    /// Only points within the maximum distance of a target point count, infinite when there are none.
+    pub rmse: f64,
+    /// The number of source points within the maximum distance of a target point
+    pub correspondences: usize,
+    /// The number of times the transform was improved
+    pub iterations: usize,
+    /// Whether the transform stopped moving within the maximum number of iterations
+    pub converged: bool,
+}
+
+/// Align the `source` points with the points of the `target` tree using point-to-point ICP
+///
+/// Every iteration pairs each moved source point with its nearest point of the target, drops
+/// the pairs further apart than `max_distance` and finds the rotation and translation that best
+/// fit the rest in the least squares sense. Stops when the fit barely moves the points, after
+/// `max_iterations`, or when too few pairs are left to fit. Works with points of two or three
+/// dimensions.
+///
+/// `point` turns coordinates into a point to search the target with.
+///
+/// ```rust
+/// use kd_tree_rs::cloud::{icp, Icp};
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let target: Vec<Point<f64>> = (0..20).map(|i| Point { x: i as f64, y: (i * i % 7) as f64 }).collect();
+/// let source: Vec<Point<f64>> = target.iter().map(|p| Point { x: p.x - 0.3, y: p.y + 0.2 }).collect();
+/// let registration = icp(&source, &KdNode::build(target), Icp::default(), |c| Point { x: c[0], y: c[1] });
+/// assert!(registration.converged && registration.rmse < 1e-9);
+/// assert!((registration.transform.translation[0] - 0.3).abs() < 1e-9);
+/// ```
+pub fn icp<T, P, F>(source: &[P], target: &KdNode<T, P>, settings: Icp, point: F) -> Registration
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+    P: KdPoint<Scalar = T>,
+    F: Fn(&[f64]) -> P,
+{
+    let dims: usize = P::DIMENSIONS;
+    assert!(
+        dims == 2 || dims == 3,
+        "Only points of two or three dimensions can be aligned"
+    );
+    let source: Vec<Vec<f64>> = source
+        .iter()
+        .map(|p| (0..dims).map(|dim| p.get(dim).into()).collect())
+        .collect();
+
+    let mut nearest: Vec<Neighbor<P>> = Vec::new();
+    let mut scratch: SearchScratch<T, P> = SearchScratch::new();
+    // The moved source points within reach of the target paired with their nearest target point.
+    let mut pairs = |transform: &Transform| -> Vec<(Vec<f64>, Vec<f64>, f64)> {
+        let mut pairs: Vec<(Vec<f64>, Vec<f64>, f64)> = Vec::new();
+        for coordinates in &source {
+            let moved: Vec<f64> = transform.apply(coordinates);
+            target.n_nearest_neighbor_into(point(&moved), 1, &mut nearest, &mut scratch);
+            if let Some(found) = nearest
+                .first()
+                .filter(|found| found.distance <= settings.max_distance)
+            {
+                let found: Vec<f64> = (0..dims).map(|dim| found.point.get(dim).into()).collect();
+                pairs.push((moved, found, nearest[0].squared_distance));
+            }
+        }
+        pairs
+    };
+
+    let mut transform: Transform = Transform::identity(dims);
+    let mut iterations: usize = 0;
+    let mut converged: bool = false;
+    while iterations < settings.max_iterations {
+        let matched = pairs(&transform);
+        if matched.len() < dims {
+            break;
+        }
+        let step: Transform = best_fit(&matched, dims);
+        transform = transform.then(&step);
+        iterations += 1;
+
+        let moved: f64 = matched
+            .iter()
+            .map(|(from, _, _)| {
+                step.apply(from)
+                    .iter()
+                    .zip(from)
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f64>()
+            })
+            .fold(0., f64::max);
+        if moved.sqrt() <= settings.tolerance {
+            converged = true;
+            break;
+        }
+    }
+
+    let matched = pairs(&transform);
+    let rmse: f64 = match matched.len() {
+        0 => f64::INFINITY,
+        n => (matched.iter().map(|(_, _, squared)| squared).sum::<f64>() / n as f64).sqrt(),
+    };
+    Registration {
+        transform,
+        rmse,
+        correspondences: matched.len(),
+        iterations,
+        converged,
+    }
+}
+
+/// The rotation and translation moving the first point of every pair closest to the second
+///
+/// In two dimensions the angle has a closed form, in three the rotation is the unit quaternion
+/// found by Horn's method.
+fn best_fit(pairs: &[(Vec<f64>, Vec<f64>, f64)], dims: usize) -> Transform {
+    let n: f64 = pairs.len() as f64;
+    let from: Vec<f64> = (0..dims)
+        .map(|dim| pairs.iter().map(|pair| pair.0[dim]).sum::<f64>() / n)
+        .collect();
+    let to: Vec<f64> = (0..dims)
+        .map(|dim| pairs.iter().map(|pair| pair.1[dim]).sum::<f64>() / n)
+        .collect();
+
+    // The cross covariance, s[i][j] sums the offsets along axis i of the first points times axis j of the second.
+    let mut s: Vec<Vec<f64>> = vec![vec![0.; dims]; dims];
+    for (a, b, _) in pairs {
+        for i in 0..dims {
+            for j in 0..dims {
+                s[i][j] += (a[i] - from[i]) * (b[j] - to[j]);
+            }
+        }
+    }
+
+    let rotation: Vec<f64> = if dims == 2 {
+        let angle: f64 = (s[0][1] - s[1][0]).atan2(s[0][0] + s[1][1]);
+        let (sin, cos) = angle.sin_cos();
+        vec![cos, -sin, sin, cos]
+    } else {
+        let n: Vec<f64> = vec![
+            s[0][0] + s[1][1] + s[2][2],
+            s[1][2] - s[2][1],
+            s[2][0] - s[0][2],
+            s[0][1] - s[1][0],
+            s[1][2] - s[2][1],
+            s[0][0] - s[1][1] - s[2][2],
+            s[0][1] + s[1][0],
+            s[2][0] + s[0][2],
+            s[2][0] - s[0][2],
+            s[0][1] + s[1][0],
+            s[1][1] - s[0][0] - s[2][2],
+            s[1][2] + s[2][1],
+            s[0][1] - s[1][0],
+            s[2][0] + s[0][2],
+            s[1][2] + s[2][1],
+            s[2][2] - s[0][0] - s[1][1],
+        ];
+        let (_, vectors) = eigen(n, 4);
+        let (w, x, y, z) = (vectors[3][0], vectors[3][1], vectors[3][2], vectors[3][3]);
+        vec![
+            1. - 2. * (y * y + z * z),
+            2. * (x * y - w * z),
+            2. * (x * z + w * y),
+            2. * (x * y + w * z),
+            1. - 2. * (x * x + z * z),
+            2. * (y * z - w * x),
+            2. * (x * z - w * y),
+            2. * (y * z + w * x),
+            1. - 2. * (x * x + y * y),
+        ]
+    };
+
+    let mut transform: Transform = Transform {
+        rotation,
+        translation: vec![0.; dims],
+    };
+    let turned: Vec<f64> = transform.apply(&from);
+    transform.translation = to
+        .iter()
+        .zip(&turned)
+        .map(|(to, turned)| to - turned)
+        .collect();
+    transform
+}
+
+/// The eigenvalues of a symmetric matrix from smallest to largest along with their unit eigenvectors
+///
+/// Uses the cyclic Jacobi method, rotating away the entries off the diagonal one at a time until
+/// they are negligible.
+fn eigen(mut matrix: Vec<f64>, n: usize) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let mut vectors: Vec<f64> = (0..n * n)
+        .map(|i| if i / n == i % n { 1. } else { 0. })
+        .collect();
+    for _ in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .map(|(p, q)| matrix[p * n + q].powi(2))
+            .sum();
+        let diagonal: f64 = (0..n).map(|p| matrix[p * n + p].powi(2)).sum();
+        if off <= f64::EPSILON * f64::EPSILON * diagonal {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                let entry: f64 = matrix[p * n + q];
+                if entry == 0. {
+                    continue;
+                }
+                // Pick the rotation that zeroes the entry at p, q.
+                let theta: f64 = (matrix[q * n + q] - matrix[p * n + p]) / (2. * entry);
+                let t: f64 = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let c: f64 = 1. / (t * t + 1.).sqrt();
+                let s: f64 = t * c;
+                for k in 0..n {
+                    let (kp, kq) = (matrix[k * n + p], matrix[k * n + q]);
+                    matrix[k * n + p] = c * kp - s * kq;
+                    matrix[k * n + q] = s * kp + c * kq;
+                }
+                for k in 0..n {
+                    let (pk, qk) = (matrix[p * n + k], matrix[q * n + k]);
+                    matrix[p * n + k] = c * pk - s * qk;
+                    matrix[q * n + k] = s * pk + c * qk;
+                }
+                for k in 0..n {
+                    let (kp, kq) = (vectors[k * n + p], vectors[k * n + q]);
+                    vectors[k * n + p] = c * kp - s * kq;
+                    vectors[k * n + q] = s * kp + c * kq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| matrix[a * n + a].total_cmp(&matrix[b * n + b]));
+    let values: Vec<f64> = order.iter().map(|i| matrix[i * n + i]).collect();
+    let columns: Vec<Vec<f64>> = order
+        .iter()
+        .map(|i| (0..n).map(|k| vectors[k * n + i]).collect())
+        .collect();
+    (values, columns)
+}
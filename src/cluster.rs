@@ -0,0 +1,486 @@
+//! Clustering the points of a tree.
+
+use crate::flat::{find, Cell, Flat};
+use crate::neighbor::Neighbor;
+use crate::point::{Indexed, KdPoint};
+use crate::search::SearchScratch;
+use crate::{KdNode, KDT};
+
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// The cluster a point was put in by [`dbscan`](fn.dbscan.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Label {
+    /// The point is too far from any cluster to belong to one
+    Noise,
+    /// The point belongs to the cluster with this id, ids count up from zero
+    Cluster(usize),
+}
+
+/// Group the points of a tree with DBSCAN
+///
+/// A point with at least `min_pts` points within `eps` of it, itself included, is a core point.
+/// Core points within `eps` of each other share a cluster, along with the other points within
+/// `eps` of them. Every other point is noise.
+///
+/// The tree is expected to come from [`build_indexed`](../enum.KdNode.html#method.build_indexed),
+/// the label of the point at index `i` of the points it was built from is at index `i` of the
+/// result. Clusters are numbered in the order of their first point.
+///
+/// ```rust
+/// use kd_tree_rs::cluster::{dbscan, Label};
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let points = vec![Point { x: 0., y: 0. }, Point { x: 1., y: 0. }, Point { x: 0., y: 1. }, Point { x: 50., y: 50. }];
+/// let labels = dbscan(&KdNode::build_indexed(points), 1.5, 3);
+/// assert_eq!(labels, vec![Label::Cluster(0), Label::Cluster(0), Label::Cluster(0), Label::Noise]);
+/// ```
+pub fn dbscan<T, P>(tree: &KdNode<T, Indexed<P>>, eps: f64, min_pts: usize) -> Vec<Label>
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+    P: KdPoint<Scalar = T>,
+{
+    let mut points: Vec<Option<Indexed<P>>> = vec![None; tree.len()];
+    for item in tree.iter() {
+        assert!(
+            item.data < points.len(),
+            "The tree must hold the indices from zero up to its length"
+        );
+        points[item.data] = Some(*item);
+    }
+
+    let mut labels: Vec<Option<Label>> = vec![None; points.len()];
+    let mut clusters: usize = 0;
+    let mut queue: Vec<usize> = Vec::new();
+    let mut neighbors: Vec<Neighbor<Indexed<P>>> = Vec::new();
+    let mut scratch: SearchScratch<T, Indexed<P>> = SearchScratch::new();
+    for (start, point) in points.iter().enumerate() {
+        let point: Indexed<P> = point.expect("Every index must be in the tree once");
+        if labels[start].is_some() {
+            continue;
+        }
+        // Only core points need their neighbors, counting is enough to tell them apart.
+        if tree.count_within_radius(point, eps) < min_pts {
+            labels[start] = Some(Label::Noise);
+            continue;
+        }
+
+        let cluster: Label = Label::Cluster(clusters);
+        clusters += 1;
+        labels[start] = Some(cluster);
+        queue.push(start);
+        while let Some(core) = queue.pop() {
+            tree.nearest_neighbor_into(points[core].unwrap(), eps, &mut neighbors, &mut scratch);
+            for neighbor in &neighbors {
+                let index: usize = neighbor.point.data;
+                match labels[index] {
+                    // Noise within reach of a core point is on the border of its cluster.
+                    Some(Label::Noise) => labels[index] = Some(cluster),
+                    Some(Label::Cluster(_)) => {}
+                    None => {
+                        labels[index] = Some(cluster);
+                        if tree.count_within_radius(neighbor.point, eps) >= min_pts {
+                            queue.push(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    labels.into_iter().map(|label| label.unwrap()).collect()
+}
+
+/// Two clusters joined by [`single_linkage`](fn.single_linkage.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Merge {
+    /// The id of one of the clusters joined
+    pub left: usize,
+    /// The id of the other cluster joined, never smaller than `left`
+    pub right: usize,
+    /// The distance between the closest points of the two clusters
+    pub distance: f64,
+    /// The number of points in the joined cluster
+    pub size: usize,
+}
+
+/// The order clusters are joined in when grouping points with single linkage.
+///
+/// The points are clusters on their own with the ids from zero up to the number of points,
+/// the same as their indices. Each merge then makes a new cluster with the next id, so the
+/// cluster made by the merge at index `i` has the id `len + i`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dendrogram {
+    /// The number of points
+    pub len: usize,
+    /// The merges from the shortest distance to the longest
+    pub merges: Vec<Merge>,
+}
+
+impl Dendrogram {
+    /// The clusters left after making every merge at or below `distance`
+    ///
+    /// Returns the cluster of every point by its index, clusters are numbered from zero in the
+    /// order of their first point.
+    pub fn cut(&self, distance: f64) -> Vec<usize> {
+        let mut parents: Vec<usize> = (0..self.len + self.merges.len()).collect();
+        for (i, merge) in self
+            .merges
+            .iter()
+            .enumerate()
+            .take_while(|(_, merge)| merge.distance <= distance)
+        {
+            parents[merge.left] = self.len + i;
+            parents[merge.right] = self.len + i;
+        }
+
+        let mut ids: Vec<Option<usize>> = vec![None; parents.len()];
+        let mut clusters: usize = 0;
+        (0..self.len)
+            .map(|point| {
+                let root: usize = find(&mut parents, point);
+                *ids[root].get_or_insert_with(|| {
+                    clusters += 1;
+                    clusters - 1
+                })
+            })
+            .collect()
+    }
+}
+
+/// Group the points of a tree with single linkage hierarchical clustering
+///
+/// Starting with every point on its own, the two clusters with the closest points are joined
+/// until one is left. These are the edges of the [`emst`](../enum.KdNode.html#method.emst)
+/// taken from shortest to longest, which is how they are found.
+///
+/// As with [`dbscan`](fn.dbscan.html) the tree is expected to come from
+/// [`build_indexed`](../enum.KdNode.html#method.build_indexed), points are given by their index.
+///
+/// ```rust
+/// use kd_tree_rs::cluster::single_linkage;
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let points = vec![Point { x: 0., y: 0. }, Point { x: 10., y: 0. }, Point { x: 1., y: 0. }];
+/// let dendrogram = single_linkage(&KdNode::build_indexed(points));
+/// assert_eq!((dendrogram.merges[0].left, dendrogram.merges[0].right), (0, 2));
+/// assert_eq!((dendrogram.merges[1].left, dendrogram.merges[1].right), (1, 3));
+/// assert_eq!(dendrogram.cut(5.), vec![0, 1, 0]);
+/// ```
+pub fn single_linkage<T: KDT, P: KdPoint<Scalar = T>>(tree: &KdNode<T, Indexed<P>>) -> Dendrogram {
+    let flat: Flat<Indexed<P>> = Flat::new(tree);
+    let mut edges: Vec<(usize, usize, f64)> = flat.spanning_tree();
+    edges.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let len: usize = flat.len();
+    // The cluster every point was last joined into, followed by the parent of every cluster.
+    let mut parents: Vec<usize> = (0..2 * len).collect();
+    let mut sizes: Vec<usize> = vec![1; 2 * len];
+    let mut merges: Vec<Merge> = Vec::with_capacity(edges.len());
+    for (i, j, distance) in edges {
+        let (i, j) = (flat.points[i].data, flat.points[j].data);
+        assert!(
+            i < len && j < len,
+            "The tree must hold the indices from zero up to its length"
+        );
+        let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+        let id: usize = len + merges.len();
+        parents[a] = id;
+        parents[b] = id;
+        sizes[id] = sizes[a] + sizes[b];
+        merges.push(Merge {
+            left: a.min(b),
+            right: a.max(b),
+            distance,
+            size: sizes[id],
+        });
+    }
+    Dendrogram { len, merges }
+}
+
+/// How [`kmeans`](fn.kmeans.html) picks the centroids it starts from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Init {
+    /// Start from these centroids, each with one coordinate for every dimension of the points
+    Centroids(Vec<Vec<f64>>),
+    /// Pick `k` of the points with k-means++, the same `seed` always picks the same points
+    PlusPlus { k: usize, seed: u64 },
+}
+
+/// The clusters found by [`kmeans`](fn.kmeans.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct KMeans {
+    /// The center of every cluster
+    pub centroids: Vec<Vec<f64>>,
+    /// The cluster of every point, in the order of the points the tree was built from
+    pub assignments: Vec<usize>,
+    /// The sum of the squared distances from every point to the center of its cluster
+    pub inertia: f64,
+    /// The number of assignment passes made, including the last one that finds the centroids
+    /// settled when they converge before `max_iterations`
+    pub iterations: usize,
+}
+
+/// Group the points of a tree into clusters with k-means
+///
+/// Runs Lloyd's algorithm until the centroids stop moving or `max_iterations` is reached.
+/// Instead of comparing every point with every centroid, each subtree is given the centroids
+/// that may be closest to some point in its bounding box, dropping the rest as it goes down
+/// the tree (Kanungo et al.). A subtree left with a single centroid is added to it at once.
+///
+/// As with [`dbscan`](fn.dbscan.html) the tree is expected to come from
+/// [`build_indexed`](../enum.KdNode.html#method.build_indexed). A cluster left without
+/// points keeps its centroid where it was.
+///
+/// ```rust
+/// use kd_tree_rs::cluster::{kmeans, Init};
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let points = vec![Point { x: 0., y: 0. }, Point { x: 0., y: 2. }, Point { x: 10., y: 0. }, Point { x: 10., y: 2. }];
+/// let clusters = kmeans(&KdNode::build_indexed(points), Init::Centroids(vec![vec![1., 1.], vec![9., 1.]]), 10);
+/// assert_eq!(clusters.centroids, vec![vec![0., 1.], vec![10., 1.]]);
+/// assert_eq!(clusters.assignments, vec![0, 0, 1, 1]);
+/// assert_eq!(clusters.inertia, 4.);
+/// ```
+pub fn kmeans<T: KDT, P: KdPoint<Scalar = T>>(
+    tree: &KdNode<T, Indexed<P>>,
+    init: Init,
+    max_iterations: usize,
+) -> KMeans {
+    let dims: usize = P::DIMENSIONS;
+    let flat: Flat<Indexed<P>> = Flat::new(tree);
+    let mut centroids: Vec<f64> = match init {
+        Init::Centroids(centroids) => {
+            assert!(
+                centroids.iter().all(|centroid| centroid.len() == dims),
+                "Centroids must have a coordinate for every dimension"
+            );
+            centroids.concat()
+        }
+        Init::PlusPlus { k, seed } => plus_plus(&flat, k, seed),
+    };
+    let k: usize = centroids.len() / dims;
+    assert!(
+        k > 0 || flat.len() == 0,
+        "k-means needs at least one centroid"
+    );
+
+    let sums: Vec<f64> = flat.sums();
+    let mut totals: Vec<f64> = vec![0.; k * dims];
+    let mut counts: Vec<usize> = vec![0; k];
+    let mut iterations: usize = 0;
+    while iterations < max_iterations {
+        filter(
+            &flat,
+            &sums,
+            &centroids,
+            &mut totals,
+            &mut counts,
+            |_, _| {},
+        );
+        let mut moved: Vec<f64> = centroids.clone();
+        for (cluster, count) in counts.iter().enumerate() {
+            if *count > 0 {
+                for dim in 0..dims {
+                    moved[cluster * dims + dim] = totals[cluster * dims + dim] / *count as f64;
+                }
+            }
+        }
+        iterations += 1;
+        if moved == centroids {
+            break;
+        }
+        centroids = moved;
+    }
+
+    let mut assignments: Vec<usize> = vec![0; flat.len()];
+    filter(
+        &flat,
+        &sums,
+        &centroids,
+        &mut totals,
+        &mut counts,
+        |cell, cluster| {
+            for point in &flat.points[cell.start..cell.end] {
+                assignments[point.data] = cluster;
+            }
+        },
+    );
+    let inertia: f64 = flat
+        .points
+        .iter()
+        .map(|item| squared_distance(&item.point, &centroids[assignments[item.data] * dims..]))
+        .sum();
+
+    KMeans {
+        centroids: centroids
+            .chunks(dims)
+            .map(|centroid| centroid.to_vec())
+            .collect(),
+        assignments,
+        inertia,
+        iterations,
+    }
+}
+
+/// The squared distance between a point and the first `DIMENSIONS` coordinates of `center`
+fn squared_distance<P: KdPoint>(point: &P, center: &[f64]) -> f64 {
+    (0..P::DIMENSIONS)
+        .map(|dim| {
+            let diff: f64 = point.get(dim).into() - center[dim];
+            diff * diff
+        })
+        .sum()
+}
+
+/// Add every point to the total and count of its closest centroid
+///
+/// `assign` is called with every cell whose points all went to the same centroid.
+fn filter<P, F>(
+    flat: &Flat<P>,
+    sums: &[f64],
+    centroids: &[f64],
+    totals: &mut [f64],
+    counts: &mut [usize],
+    mut assign: F,
+) where
+    P: KdPoint,
+    F: FnMut(Cell, usize),
+{
+    let dims: usize = P::DIMENSIONS;
+    let k: usize = counts.len();
+    totals.fill(0.);
+    counts.fill(0);
+
+    // The candidates of every cell on the stack are a range of `candidates`. A cell's range is
+    // added after those of the cells below it, so it can be cut back once a cell is reached.
+    let mut candidates: Vec<usize> = (0..k).collect();
+    let mut stack: Vec<(Cell, usize, usize)> =
+        flat.root().map(|root| (root, 0, k)).into_iter().collect();
+    let mut middle: Vec<f64> = vec![0.; dims];
+    while let Some((cell, start, end)) = stack.pop() {
+        candidates.truncate(end);
+
+        if cell.len() == 1 {
+            let point: &P = &flat.points[cell.start];
+            let closest: usize = closest(&candidates[start..end], centroids, dims, |centroid| {
+                squared_distance(point, centroid)
+            });
+            for dim in 0..dims {
+                totals[closest * dims + dim] += point.get(dim).into();
+            }
+            counts[closest] += 1;
+            assign(cell, closest);
+            continue;
+        }
+
+        for (dim, value) in middle.iter_mut().enumerate() {
+            let (lower, upper) = flat.bounds(cell, dim);
+            *value = (lower + upper) / 2.;
+        }
+        let closest: usize = closest(&candidates[start..end], centroids, dims, |centroid| {
+            distance_between(&middle, centroid)
+        });
+
+        // Drop the centroids that are farther than the closest one from every corner of the box.
+        // Only the corner farthest in their direction from the closest centroid needs checking.
+        let kept: usize = candidates.len();
+        for index in start..end {
+            let candidate: usize = candidates[index];
+            let centroid: &[f64] = &centroids[candidate * dims..(candidate + 1) * dims];
+            let best: &[f64] = &centroids[closest * dims..(closest + 1) * dims];
+            for (dim, value) in middle.iter_mut().enumerate() {
+                let (lower, upper) = flat.bounds(cell, dim);
+                *value = if centroid[dim] > best[dim] {
+                    upper
+                } else {
+                    lower
+                };
+            }
+            if candidate == closest
+                || distance_between(&middle, centroid) < distance_between(&middle, best)
+            {
+                candidates.push(candidate);
+            }
+        }
+
+        if candidates.len() - kept == 1 {
+            for dim in 0..dims {
+                totals[closest * dims + dim] += sums[cell.start * dims + dim];
+            }
+            counts[closest] += cell.len();
+            assign(cell, closest);
+            continue;
+        }
+        for child in flat.children(cell).into_iter().flatten() {
+            stack.push((child, kept, candidates.len()));
+        }
+    }
+}
+
+/// The candidate centroid with the smallest `distance`
+fn closest<F: Fn(&[f64]) -> f64>(
+    candidates: &[usize],
+    centroids: &[f64],
+    dims: usize,
+    distance: F,
+) -> usize {
+    let mut closest: (usize, f64) = (candidates[0], f64::INFINITY);
+    for candidate in candidates {
+        let dis: f64 = distance(&centroids[candidate * dims..(candidate + 1) * dims]);
+        if dis < closest.1 {
+            closest = (*candidate, dis);
+        }
+    }
+    closest.0
+}
+
+/// The squared distance between two points given by their coordinates
+fn distance_between(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// Pick `k` of the points as centroids, each with a chance in proportion to its squared
+/// distance from the closest centroid picked so far
+fn plus_plus<P: KdPoint>(flat: &Flat<P>, k: usize, mut seed: u64) -> Vec<f64> {
+    let dims: usize = P::DIMENSIONS;
+    let mut random = || {
+        // SplitMix64
+        seed = seed.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z: u64 = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        (z ^ (z >> 31)) as f64 / u64::MAX as f64
+    };
+
+    let mut centroids: Vec<f64> = Vec::with_capacity(k * dims);
+    if flat.len() == 0 {
+        return centroids;
+    }
+    let mut nearest: Vec<f64> = vec![f64::INFINITY; flat.len()];
+    let mut chosen: usize = ((random() * flat.len() as f64) as usize).min(flat.len() - 1);
+    for _ in 0..k {
+        let point: &P = &flat.points[chosen];
+        let centroid: Vec<f64> = (0..dims).map(|dim| point.get(dim).into()).collect();
+        for (dis, point) in nearest.iter_mut().zip(&flat.points) {
+            *dis = dis.min(squared_distance(point, &centroid));
+        }
+        centroids.extend(centroid);
+
+        let total: f64 = nearest.iter().sum();
+        if total == 0. {
+            // Every point sits on a centroid already, any of them will do.
+            chosen = ((random() * flat.len() as f64) as usize).min(flat.len() - 1);
+            continue;
+        }
+        let mut target: f64 = random() * total;
+        chosen = nearest
+            .iter()
+            .position(|dis| {
+                target -= dis;
+                target < 0.
+            })
+            .unwrap_or(flat.len() - 1);
+    }
+    centroids
+}
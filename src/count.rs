@@ -0,0 +1,66 @@
+use crate::point::{distance, KdPoint};
+use crate::walk::{max_distance, min_distance};
+use crate::{KdNode, KDT};
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Count the points within `radius` of the origin
+    ///
+    /// The radius is inclusive, the same as [`nearest_neighbor`](#method.nearest_neighbor), but no
+    /// points are collected. A subtree lying entirely within the radius is counted by its size
+    /// without looking at its points.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 1, y: 0 }, Point { x: 10, y: 0 }]);
+    /// assert_eq!(node.count_within_radius(Point { x: 0, y: 0 }, 1.), 2);
+    /// ```
+    pub fn count_within_radius(&self, origin: P, radius: f64) -> usize {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let mut count: usize = 0;
+        self.walk(
+            &mut count,
+            // Search the side of the split holding the origin first.
+            |dim, split| origin.get(dim).into() >= split,
+            |_, lower, upper| min_distance(&origin, lower, upper) > radius,
+            |count, node, point, lower, upper| {
+                if max_distance(&origin, lower, upper) <= radius {
+                    *count += node.len();
+                    return false;
+                }
+                if distance(&origin, point) <= radius {
+                    *count += 1;
+                }
+                true
+            },
+        );
+        count
+    }
+
+    /// Check if any point is within `radius` of the origin
+    ///
+    /// The search stops at the first point found.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 10, y: 0 }]);
+    /// assert!(node.any_within_radius(Point { x: 8, y: 0 }, 2.));
+    /// assert!(!node.any_within_radius(Point { x: 5, y: 0 }, 2.));
+    /// ```
+    pub fn any_within_radius(&self, origin: P, radius: f64) -> bool {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let mut found: bool = false;
+        self.walk(
+            &mut found,
+            |dim, split| origin.get(dim).into() >= split,
+            // Once a point is found every remaining subtree is skipped.
+            |found, lower, upper| *found || min_distance(&origin, lower, upper) > radius,
+            |found, _, point, _, _| {
+                *found = distance(&origin, point) <= radius;
+                !*found
+            },
+        );
+        found
+    }
+}
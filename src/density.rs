@@ -0,0 +1,189 @@
+//! Local density and outlier scores of the points of a tree.
+
+use crate::flat::Flat;
+use crate::neighbor::Neighbor;
+use crate::point::{Indexed, KdPoint};
+use crate::{KdNode, KDT};
+
+use std::f64::consts::PI;
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// The kernel used by [`kernel_density`](../enum.KdNode.html#method.kernel_density).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kernel {
+    /// The normal distribution, ignoring points more than `cutoff` bandwidths away
+    Gaussian { cutoff: f64 },
+    /// The parabola `1 - u²`, zero for points more than one bandwidth away
+    Epanechnikov,
+}
+
+impl Kernel {
+    /// The radius the kernel is evaluated within, in bandwidths
+    fn support(&self) -> f64 {
+        match self {
+            Kernel::Gaussian { cutoff } => *cutoff,
+            Kernel::Epanechnikov => 1.,
+        }
+    }
+
+    /// The kernel at `u` bandwidths from its center, scaled to integrate to one over `dims` dimensions
+    fn weight(&self, u: f64, dims: usize) -> f64 {
+        match self {
+            Kernel::Gaussian { .. } => (-u * u / 2.).exp() / (2. * PI).powf(dims as f64 / 2.),
+            Kernel::Epanechnikov => {
+                // The volume of the unit ball, from the balls two dimensions down.
+                let mut volume: f64 = [1., 2.][dims % 2];
+                for d in (dims % 2 + 2..=dims).step_by(2) {
+                    volume *= 2. * PI / d as f64;
+                }
+                (dims as f64 + 2.) / (2. * volume) * (1. - u * u).max(0.)
+            }
+        }
+    }
+}
+
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+    P: KdPoint<Scalar = T>,
+{
+    /// Estimate the density of the points of the tree at `origin`
+    ///
+    /// Every point within reach of the origin adds the kernel at its distance, measured in
+    /// `bandwidth`, and the sum is divided by the number of points so it integrates to one
+    /// over the whole space. Only the points found by a radius search around the origin are
+    /// looked at, so a Gaussian kernel is an approximation that improves with its cutoff.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::density::Kernel;
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![Point { x: 0., y: 0. }, Point { x: 10., y: 0. }]);
+    /// let near = node.kernel_density(Point { x: 0.5, y: 0. }, Kernel::Epanechnikov, 1.);
+    /// let far = node.kernel_density(Point { x: 5., y: 0. }, Kernel::Epanechnikov, 1.);
+    /// assert!(near > 0. && far == 0.);
+    /// ```
+    pub fn kernel_density(&self, origin: P, kernel: Kernel, bandwidth: f64) -> f64 {
+        assert!(bandwidth > 0., "The bandwidth must be positive");
+        if self.is_empty() {
+            return 0.;
+        }
+        let near: Vec<Neighbor<P>> =
+            self.nearest_neighbor_with_distance(origin, kernel.support() * bandwidth);
+        let sum: f64 = near
+            .iter()
+            .map(|neighbor| kernel.weight(neighbor.distance / bandwidth, P::DIMENSIONS))
+            .sum();
+        sum / (self.len() as f64 * bandwidth.powi(P::DIMENSIONS as i32))
+    }
+}
+
+/// Find the distance from every point to its `k`-th nearest other point
+///
+/// The tree is expected to come from [`build_indexed`](../enum.KdNode.html#method.build_indexed),
+/// the distance of the point at index `i` of the points it was built from is at index `i` of
+/// the result. When the tree holds `k` points or fewer the farthest other point is used, a
+/// point on its own has an infinite distance.
+///
+/// ```rust
+/// use kd_tree_rs::density::k_distance;
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let points = vec![Point { x: 0., y: 0. }, Point { x: 1., y: 0. }, Point { x: 3., y: 0. }];
+/// assert_eq!(k_distance(&KdNode::build_indexed(points), 2), vec![3., 2., 3.]);
+/// ```
+pub fn k_distance<T: KDT, P: KdPoint<Scalar = T>>(
+    tree: &KdNode<T, Indexed<P>>,
+    k: usize,
+) -> Vec<f64> {
+    let flat: Flat<Indexed<P>> = Flat::new(tree);
+    let nearest: Vec<Vec<(usize, f64)>> = nearest_others(&flat, k);
+    let distances: Vec<f64> = nearest
+        .iter()
+        .map(|neighbors| farthest(neighbors))
+        .collect();
+    by_index(&flat, distances)
+}
+
+/// Score how much every point is an outlier with the Local Outlier Factor
+///
+/// The reachability of a point from a neighbor is their distance, but never less than the
+/// [`k_distance`](fn.k_distance.html) of the neighbor. The local density of a point is one over
+/// the mean reachability from its `k` nearest neighbors, and its score is the mean density of
+/// those neighbors divided by its own. Points about as dense as their neighbors score around
+/// one, outliers score well above it.
+///
+/// Scores are returned by index as with [`k_distance`](fn.k_distance.html). Points stored more
+/// than `k` times have an infinite density, a point with the same density as its neighbors
+/// scores one even then.
+///
+/// ```rust
+/// use kd_tree_rs::density::lof;
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let mut points: Vec<Point<f64>> = (0..9).map(|i| Point { x: (i % 3) as f64, y: (i / 3) as f64 }).collect();
+/// points.push(Point { x: 20., y: 20. });
+/// let scores = lof(&KdNode::build_indexed(points), 3);
+/// assert!(scores[..9].iter().all(|score| *score < 1.5));
+/// assert!(scores[9] > 5.);
+/// ```
+pub fn lof<T: KDT, P: KdPoint<Scalar = T>>(tree: &KdNode<T, Indexed<P>>, k: usize) -> Vec<f64> {
+    let flat: Flat<Indexed<P>> = Flat::new(tree);
+    let nearest: Vec<Vec<(usize, f64)>> = nearest_others(&flat, k);
+    let distances: Vec<f64> = nearest
+        .iter()
+        .map(|neighbors| farthest(neighbors))
+        .collect();
+
+    let densities: Vec<f64> = nearest
+        .iter()
+        .map(|neighbors| {
+            let reach: f64 = neighbors
+                .iter()
+                .map(|(j, dis)| dis.max(distances[*j]))
+                .sum();
+            neighbors.len() as f64 / reach
+        })
+        .collect();
+
+    let scores: Vec<f64> = nearest
+        .iter()
+        .zip(&densities)
+        .map(|(neighbors, density)| {
+            if neighbors.is_empty() {
+                return 1.;
+            }
+            let around: f64 =
+                neighbors.iter().map(|(j, _)| densities[*j]).sum::<f64>() / neighbors.len() as f64;
+            match (around.is_infinite(), density.is_infinite()) {
+                (true, true) => 1.,
+                _ => around / density,
+            }
+        })
+        .collect();
+    by_index(&flat, scores)
+}
+
+/// The `k` nearest other points of every point, by their index in the flattened tree
+fn nearest_others<P: KdPoint>(flat: &Flat<Indexed<P>>, k: usize) -> Vec<Vec<(usize, f64)>> {
+    assert!(k > 0, "At least one neighbor is needed");
+    flat.nearest_in(flat, k, true)
+}
+
+fn farthest(neighbors: &[(usize, f64)]) -> f64 {
+    neighbors.last().map_or(f64::INFINITY, |(_, dis)| *dis)
+}
+
+/// Put values given for the points of the flattened tree in the order of their indices
+fn by_index<P: KdPoint>(flat: &Flat<Indexed<P>>, values: Vec<f64>) -> Vec<f64> {
+    let mut ordered: Vec<f64> = vec![f64::NAN; values.len()];
+    for (point, value) in flat.points.iter().zip(values) {
+        assert!(
+            point.data < ordered.len(),
+            "The tree must hold the indices from zero up to its length"
+        );
+        ordered[point.data] = value;
+    }
+    ordered
+}
@@ -1,19 +1,40 @@
-#[derive(Debug, PartialEq)]
-pub enum Dim {
-    X,
-    Y,
-}
+use std::fmt;
+
+/// The axis a node splits its children on.
+///
+/// This is an index into the coordinates of a [`KdPoint`](../point/trait.KdPoint.html),
+/// the first three axes are available as `Dim::X`, `Dim::Y` and `Dim::Z`.
+#[derive(PartialEq, Eq, Copy, Clone, Hash)]
+pub struct Dim(usize);
 
 impl Dim {
-    const DIMENSIONS: usize = 2;
+    pub const X: Dim = Dim(0);
+    pub const Y: Dim = Dim(1);
+    pub const Z: Dim = Dim(2);
+
+    /// Create a dimension from an axis index
+    pub fn new(index: usize) -> Dim {
+        Dim(index)
+    }
+
+    /// The axis index of this dimension
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    pub(crate) fn from_depth(n: usize, dimensions: usize) -> Dim {
+        assert!(dimensions > 0, "Points must have at least one dimension");
+        Dim(n.rem_euclid(dimensions))
+    }
+}
 
-    pub(crate) fn from_depth(mut n: usize) -> Dim {
-        n = n.rem_euclid(Dim::DIMENSIONS);
-        assert!(n < Dim::DIMENSIONS);
-        match n {
-            0 => Dim::X,
-            1 => Dim::Y,
-            _ => panic!("Higher dimensions not implemented"),
+impl fmt::Debug for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => write!(f, "X"),
+            1 => write!(f, "Y"),
+            2 => write!(f, "Z"),
+            n => write!(f, "Dim({})", n),
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::neighbor::Neighbor;
+use crate::point::{squared_distance, KdPoint};
+use crate::walk::max_distance;
+use crate::{KdNode, KDT};
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find the point in the tree farthest from the origin
+    ///
+    /// Returns `None` when the tree is empty.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 3, y: 4 }, Point { x: 1, y: 1 }]);
+    /// let farthest = node.farthest(Point { x: 0, y: 0 }).unwrap();
+    /// assert_eq!(farthest.point, Point { x: 3, y: 4 });
+    /// assert_eq!(farthest.distance, 5.);
+    /// ```
+    pub fn farthest(&self, origin: P) -> Option<Neighbor<P>> {
+        self.k_farthest(origin, 1).pop()
+    }
+
+    /// Find the `k` points in the tree farthest from the origin, the farthest first
+    ///
+    /// Every node splits the space around it in two, a subtree is skipped when the farthest
+    /// corner of the region it lies in, or of its bounding box when it stores one, is closer to
+    /// the origin than the `k` points found so far.
+    pub fn k_farthest(&self, origin: P, k: usize) -> Vec<Neighbor<P>> {
+        let mut farthest: Vec<Neighbor<P>> = Vec::with_capacity(k);
+        if k == 0 {
+            return farthest;
+        }
+
+        self.walk(
+            &mut farthest,
+            // The child on the other side of the split from the origin likely reaches farther.
+            |dim, split| origin.get(dim).into() < split,
+            |farthest, lower, upper| {
+                farthest.len() == k
+                    && max_distance(&origin, lower, upper) < farthest[k - 1].distance
+            },
+            |farthest, _, point, _, _| {
+                let squared: f64 = squared_distance(&origin, point);
+                if farthest.len() < k || squared > farthest[k - 1].squared_distance {
+                    let index: usize =
+                        farthest.partition_point(|other| other.squared_distance >= squared);
+                    farthest.insert(index, Neighbor::new(*point, squared));
+                    farthest.truncate(k);
+                }
+                true
+            },
+        );
+        farthest
+    }
+}
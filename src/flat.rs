@@ -0,0 +1,790 @@
+use crate::point::{distance, KdPoint};
+use crate::{KdNode, Node};
+
+/// Cell pairs with at most this many point pairs between them are compared point by point.
+const LEAF_PAIRS: usize = 64;
+
+/// Query cells with at most this many points search for their nearest neighbors together.
+const BATCH_SIZE: usize = 16;
+
+/// A range of points in a [`Flat`] tree, either a whole subtree or only the point at `start`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Cell {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Cell {
+    pub(crate) fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// The points of a tree in pre-order along with the bounding box of every subtree.
+///
+/// The subtree of the point at `i` covers `i..i + size[i]`, its left child starts at `i + 1`
+/// and its right child straight after the left subtree. Walking two subtrees at once is much
+/// simpler over these indices than over the boxed nodes.
+#[derive(Debug)]
+pub(crate) struct Flat<P: KdPoint> {
+    pub(crate) points: Vec<P>,
+    size: Vec<usize>,
+    left: Vec<usize>,
+    /// `DIMENSIONS` lower bounds for each subtree, one after the other
+    lower: Vec<f64>,
+    /// `DIMENSIONS` upper bounds for each subtree, one after the other
+    upper: Vec<f64>,
+}
+
+/// What a round of [`Flat::spanning_tree`] knows about the points, by their index.
+struct Round {
+    /// The component of every point, given by one of its points
+    component: Vec<usize>,
+    /// The component of every subtree, `None` when its points are in more than one
+    uniform: Vec<Option<usize>>,
+    /// The longest shortest edge found so far leaving the component of any point of every subtree
+    bound: Vec<f64>,
+    /// The shortest edge found so far leaving every component
+    best: Vec<Option<(f64, usize, usize)>>,
+}
+
+/// A step of the walk in [`Flat::spanning_tree`].
+enum Task {
+    /// Look for edges between the points of two cells
+    Visit(Cell, Cell),
+    /// Update the bound of a cell once the pairs below it are done
+    Refresh(Cell),
+}
+
+impl Round {
+    fn uniform<P: KdPoint>(&self, flat: &Flat<P>, cell: Cell) -> Option<usize> {
+        if cell.len() == flat.size[cell.start] {
+            self.uniform[cell.start]
+        } else {
+            Some(self.component[cell.start])
+        }
+    }
+
+    fn own(&self, i: usize) -> f64 {
+        self.best[self.component[i]].map_or(f64::INFINITY, |best| best.0)
+    }
+
+    /// The length an edge leaving the cell needs to be below to improve any of its points
+    ///
+    /// The bound of a subtree is refreshed from those of its children, which may be out of date
+    /// but are never too small as edges only get shorter during a round. Subtrees in a single
+    /// component are never walked into and use the bound of the component instead.
+    fn bound<P: KdPoint>(&mut self, flat: &Flat<P>, cell: Cell) -> f64 {
+        if cell.len() < flat.size[cell.start] {
+            return self.own(cell.start);
+        }
+        self.refresh(flat, cell.start)
+    }
+
+    fn refresh<P: KdPoint>(&mut self, flat: &Flat<P>, i: usize) -> f64 {
+        let bound: f64 = match self.uniform[i] {
+            Some(_) => self.own(i),
+            None => flat
+                .subtrees(i)
+                .map(|child| self.cached(child))
+                .fold(self.own(i), f64::max),
+        };
+        self.bound[i] = bound;
+        bound
+    }
+
+    /// The last bound of a subtree, those in a single component are always up to date
+    fn cached(&self, i: usize) -> f64 {
+        match self.uniform[i] {
+            Some(_) => self.own(i),
+            None => self.bound[i],
+        }
+    }
+
+    /// Set the bound of every subtree in the cell from its points
+    fn settle<P: KdPoint>(&mut self, flat: &Flat<P>, cell: Cell) {
+        if cell.len() == flat.size[cell.start] {
+            for i in (cell.start..cell.end).rev() {
+                self.refresh(flat, i);
+            }
+        }
+    }
+}
+
+/// The root of the set holding `i`, pointing every set on the way straight at it
+pub(crate) fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root: usize = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i: usize = i;
+    while parents[i] != root {
+        i = std::mem::replace(&mut parents[i], root);
+    }
+    root
+}
+
+impl<P: KdPoint> Flat<P> {
+    pub(crate) fn new(tree: &KdNode<P::Scalar, P>) -> Self {
+        let n: usize = tree.len();
+        let mut points: Vec<P> = Vec::with_capacity(n);
+        let mut size: Vec<usize> = Vec::with_capacity(n);
+        let mut left: Vec<usize> = Vec::with_capacity(n);
+        let mut stack: Vec<&KdNode<P::Scalar, P>> = vec![tree];
+        while let Some(node) = stack.pop() {
+            if let Node {
+                point,
+                left: left_node,
+                right: right_node,
+                size: node_size,
+                ..
+            } = node
+            {
+                points.push(*point);
+                size.push(*node_size);
+                left.push(left_node.len());
+                stack.push(right_node);
+                stack.push(left_node);
+            }
+        }
+
+        let dims: usize = P::DIMENSIONS;
+        let mut lower: Vec<f64> = Vec::with_capacity(n * dims);
+        for point in &points {
+            lower.extend((0..dims).map(|dim| -> f64 { point.get(dim).into() }));
+        }
+        let mut upper: Vec<f64> = lower.clone();
+
+        // Children come after their parent so every child box is complete before it is merged.
+        for i in (0..n).rev() {
+            let split: usize = i + 1 + left[i];
+            let children = [(left[i] > 0).then_some(i + 1), (split < i + size[i]).then_some(split)];
+            for child in children.into_iter().flatten() {
+                for dim in 0..dims {
+                    lower[i * dims + dim] = lower[i * dims + dim].min(lower[child * dims + dim]);
+                    upper[i * dims + dim] = upper[i * dims + dim].max(upper[child * dims + dim]);
+                }
+            }
+        }
+
+        Flat {
+            points,
+            size,
+            left,
+            lower,
+            upper,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// The whole tree, `None` when it is empty
+    pub(crate) fn root(&self) -> Option<Cell> {
+        (!self.points.is_empty()).then_some(Cell {
+            start: 0,
+            end: self.len(),
+        })
+    }
+
+    /// Split a cell into the point at its start and its two subtrees.
+    ///
+    /// A cell holding a single point has no children.
+    pub(crate) fn children(&self, cell: Cell) -> [Option<Cell>; 3] {
+        if cell.len() == 1 {
+            return [None; 3];
+        }
+        let split: usize = cell.start + 1 + self.left[cell.start];
+        [
+            Some(Cell {
+                start: cell.start,
+                end: cell.start + 1,
+            }),
+            (split > cell.start + 1).then_some(Cell {
+                start: cell.start + 1,
+                end: split,
+            }),
+            (cell.end > split).then_some(Cell {
+                start: split,
+                end: cell.end,
+            }),
+        ]
+    }
+
+    /// The start of the subtrees below the point at `i`
+    fn subtrees(&self, i: usize) -> impl Iterator<Item = usize> {
+        let split: usize = i + 1 + self.left[i];
+        [(self.left[i] > 0).then_some(i + 1), (split < i + self.size[i]).then_some(split)]
+            .into_iter()
+            .flatten()
+    }
+
+    /// The sum of the points of every subtree, `DIMENSIONS` coordinates for each one after the other
+    pub(crate) fn sums(&self) -> Vec<f64> {
+        let dims: usize = P::DIMENSIONS;
+        let mut sums: Vec<f64> = Vec::with_capacity(self.len() * dims);
+        for point in &self.points {
+            sums.extend((0..dims).map(|dim| -> f64 { point.get(dim).into() }));
+        }
+
+        // Children come after their parent so every child sum is complete before it is added.
+        for i in (0..self.len()).rev() {
+            for child in self.subtrees(i) {
+                for dim in 0..dims {
+                    sums[i * dims + dim] += sums[child * dims + dim];
+                }
+            }
+        }
+        sums
+    }
+
+    /// The lower and upper bound of the cell along `dim`
+    pub(crate) fn bounds(&self, cell: Cell, dim: usize) -> (f64, f64) {
+        if cell.len() == self.size[cell.start] {
+            let index: usize = cell.start * P::DIMENSIONS + dim;
+            (self.lower[index], self.upper[index])
+        } else {
+            let value: f64 = self.points[cell.start].get(dim).into();
+            (value, value)
+        }
+    }
+
+    /// The smallest distance between the points of cell `a` and those of cell `b` in `other`
+    pub(crate) fn min_distance(&self, a: Cell, other: &Flat<P>, b: Cell) -> f64 {
+        (0..P::DIMENSIONS)
+            .map(|dim| {
+                let (a_lower, a_upper) = self.bounds(a, dim);
+                let (b_lower, b_upper) = other.bounds(b, dim);
+                let gap: f64 = (a_lower - b_upper).max(b_lower - a_upper).max(0.);
+                gap * gap
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// The largest distance between the points of cell `a` and those of cell `b` in `other`
+    pub(crate) fn max_distance(&self, a: Cell, other: &Flat<P>, b: Cell) -> f64 {
+        (0..P::DIMENSIONS)
+            .map(|dim| {
+                let (a_lower, a_upper) = self.bounds(a, dim);
+                let (b_lower, b_upper) = other.bounds(b, dim);
+                let span: f64 = (a_upper - b_lower).abs().max((b_upper - a_lower).abs());
+                span * span
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Every pair of points within `radius` of each other as `(i, j, distance)` with `i < j`
+    pub(crate) fn pairs_within(&self, radius: f64) -> Vec<(usize, usize, f64)> {
+        self.pairs_between(self, radius)
+    }
+
+    /// Every point `i` of this tree and `j` of `other` within `radius` of each other as `(i, j, distance)`
+    ///
+    /// When `other` is this tree each pair of distinct points is returned once, with `i < j`.
+    pub(crate) fn pairs_between(&self, other: &Flat<P>, radius: f64) -> Vec<(usize, usize, f64)> {
+        let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
+        let mut stack: Vec<(Cell, Cell)> = self.root_pair(other).into_iter().collect();
+        while let Some(task) = stack.pop() {
+            self.pairs_step(other, task, radius, &mut stack, &mut pairs);
+        }
+        pairs
+    }
+
+    /// The same as `pairs_within` but splits the walk between the threads of the rayon pool
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_pairs_within(&self, radius: f64) -> Vec<(usize, usize, f64)>
+    where
+        P: Sync,
+    {
+        self.par_pairs_between(self, radius)
+    }
+
+    /// The same as `pairs_between` but splits the walk between the threads of the rayon pool
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_pairs_between(&self, other: &Flat<P>, radius: f64) -> Vec<(usize, usize, f64)>
+    where
+        P: Sync,
+    {
+        use rayon::prelude::*;
+        use std::collections::VecDeque;
+
+        // Expand the walk breadth first until there are enough pairs of cells to keep every thread busy.
+        let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
+        let mut tasks: VecDeque<(Cell, Cell)> = self.root_pair(other).into_iter().collect();
+        let mut expanded: Vec<(Cell, Cell)> = Vec::new();
+        while tasks.len() < rayon::current_num_threads() * 16 {
+            match tasks.pop_front() {
+                Some(task) => self.pairs_step(other, task, radius, &mut expanded, &mut pairs),
+                None => break,
+            }
+            tasks.extend(expanded.drain(..));
+        }
+
+        let found: Vec<Vec<(usize, usize, f64)>> = tasks
+            .into_par_iter()
+            .map(|task| {
+                let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
+                let mut stack: Vec<(Cell, Cell)> = vec![task];
+                while let Some(task) = stack.pop() {
+                    self.pairs_step(other, task, radius, &mut stack, &mut pairs);
+                }
+                pairs
+            })
+            .collect();
+        pairs.extend(found.into_iter().flatten());
+        pairs
+    }
+
+    /// The roots of both trees, `None` when either is empty
+    fn root_pair(&self, other: &Flat<P>) -> Option<(Cell, Cell)> {
+        Some((self.root()?, other.root()?))
+    }
+
+    /// Compare the points of two cells, or push the pairs of their children to compare later.
+    ///
+    /// Within a single tree the cells are either the same or do not overlap, so every pair of
+    /// points is seen once.
+    fn pairs_step(
+        &self,
+        other: &Flat<P>,
+        (a, b): (Cell, Cell),
+        radius: f64,
+        stack: &mut Vec<(Cell, Cell)>,
+        pairs: &mut Vec<(usize, usize, f64)>,
+    ) {
+        let same_tree: bool = std::ptr::eq(self, other);
+        if same_tree && a == b {
+            if a.len() * (a.len() - 1) / 2 <= LEAF_PAIRS {
+                for i in a.start..a.end {
+                    for j in i + 1..a.end {
+                        self.push_if_within(i, other, j, radius, pairs);
+                    }
+                }
+            } else {
+                let children: [Option<Cell>; 3] = self.children(a);
+                for (k, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        stack.push((*child, *child));
+                        for sibling in children[k + 1..].iter().flatten() {
+                            stack.push((*child, *sibling));
+                        }
+                    }
+                }
+            }
+            return;
+        }
+
+        if self.min_distance(a, other, b) > radius {
+            return;
+        }
+
+        if a.len() * b.len() <= LEAF_PAIRS || self.max_distance(a, other, b) <= radius {
+            for i in a.start..a.end {
+                for j in b.start..b.end {
+                    if same_tree {
+                        self.push_if_within(i.min(j), other, i.max(j), radius, pairs);
+                    } else {
+                        self.push_if_within(i, other, j, radius, pairs);
+                    }
+                }
+            }
+        } else if a.len() >= b.len() {
+            stack.extend(
+                self.children(a)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| (child, b)),
+            );
+        } else {
+            stack.extend(
+                other
+                    .children(b)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| (a, child)),
+            );
+        }
+    }
+
+    /// The closest point `i` of this tree and `j` of `other` as `(i, j, distance)`
+    ///
+    /// When `other` is this tree the closest pair of distinct points is found instead.
+    pub(crate) fn closest_between(&self, other: &Flat<P>) -> Option<(usize, usize, f64)> {
+        let same_tree: bool = std::ptr::eq(self, other);
+        let mut closest: Option<(usize, usize, f64)> = None;
+        let mut stack: Vec<(Cell, Cell, f64)> = self
+            .root_pair(other)
+            .map(|(a, b)| (a, b, 0.))
+            .into_iter()
+            .collect();
+        while let Some((a, b, min)) = stack.pop() {
+            if closest.is_some_and(|(_, _, best)| min >= best) {
+                continue;
+            }
+
+            if a.len() * b.len() <= LEAF_PAIRS {
+                for i in a.start..a.end {
+                    for j in b.start..b.end {
+                        // Within one tree the cells are either the same or do not overlap.
+                        if same_tree && a == b && j <= i {
+                            continue;
+                        }
+                        let dis: f64 = distance(&self.points[i], &other.points[j]);
+                        if closest.is_none_or(|(_, _, best)| dis < best) {
+                            closest = Some(if same_tree {
+                                (i.min(j), i.max(j), dis)
+                            } else {
+                                (i, j, dis)
+                            });
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let mut children: Vec<(Cell, Cell)> = Vec::with_capacity(6);
+            if same_tree && a == b {
+                let cells: [Option<Cell>; 3] = self.children(a);
+                for (k, child) in cells.iter().enumerate() {
+                    if let Some(child) = child {
+                        children.push((*child, *child));
+                        children.extend(
+                            cells[k + 1..]
+                                .iter()
+                                .flatten()
+                                .map(|sibling| (*child, *sibling)),
+                        );
+                    }
+                }
+            } else if a.len() >= b.len() {
+                children.extend(
+                    self.children(a)
+                        .into_iter()
+                        .flatten()
+                        .map(|child| (child, b)),
+                );
+            } else {
+                children.extend(
+                    other
+                        .children(b)
+                        .into_iter()
+                        .flatten()
+                        .map(|child| (a, child)),
+                );
+            }
+
+            // Search the closest pair of cells first so the best distance shrinks quickly.
+            let mut children: Vec<(Cell, Cell, f64)> = children
+                .into_iter()
+                .map(|(a, b)| {
+                    (
+                        a,
+                        b,
+                        if same_tree && a == b {
+                            0.
+                        } else {
+                            self.min_distance(a, other, b)
+                        },
+                    )
+                })
+                .collect();
+            children.sort_by(|l, r| r.2.total_cmp(&l.2));
+            stack.extend(children);
+        }
+        closest
+    }
+
+    /// The edges of the euclidean minimum spanning tree as `(i, j, distance)` with `i < j`
+    ///
+    /// Each round of Borůvka's algorithm walks the tree against itself to find the shortest edge
+    /// leaving every component, then joins the components along them. Edges of the same length
+    /// are told apart by their points so the joins never close a cycle.
+    pub(crate) fn spanning_tree(&self) -> Vec<(usize, usize, f64)> {
+        let n: usize = self.len();
+        let mut parents: Vec<usize> = (0..n).collect();
+        let mut edges: Vec<(usize, usize, f64)> = Vec::with_capacity(n.saturating_sub(1));
+        let mut round: Round = Round {
+            component: vec![0; n],
+            uniform: vec![None; n],
+            bound: vec![f64::INFINITY; n],
+            best: vec![None; n],
+        };
+        while edges.len() + 1 < n {
+            for i in 0..n {
+                round.component[i] = find(&mut parents, i);
+            }
+            // A subtree is uniform when all of its points are in the same component.
+            for i in (0..n).rev() {
+                round.uniform[i] = Some(round.component[i]);
+                for child in self.subtrees(i) {
+                    if round.uniform[child] != round.uniform[i] {
+                        round.uniform[i] = None;
+                    }
+                }
+            }
+            round.best.fill(None);
+            round.bound.fill(f64::INFINITY);
+
+            let mut stack: Vec<Task> = self
+                .root()
+                .map(|root| Task::Visit(root, root))
+                .into_iter()
+                .collect();
+            while let Some(task) = stack.pop() {
+                match task {
+                    Task::Visit(a, b) => self.spanning_step(a, b, &mut round, &mut stack),
+                    Task::Refresh(cell) => {
+                        round.bound(self, cell);
+                    }
+                }
+            }
+
+            for component in 0..n {
+                if let Some((dis, i, j)) = round.best[component] {
+                    let (root_i, root_j) = (find(&mut parents, i), find(&mut parents, j));
+                    if root_i != root_j {
+                        parents[root_i] = root_j;
+                        edges.push((i, j, dis));
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    fn spanning_step(&self, a: Cell, b: Cell, round: &mut Round, stack: &mut Vec<Task>) {
+        let (uniform_a, uniform_b) = (round.uniform(self, a), round.uniform(self, b));
+        if uniform_a.is_some() && uniform_a == uniform_b {
+            return;
+        }
+        let min: f64 = if a == b {
+            0.
+        } else {
+            self.min_distance(a, self, b)
+        };
+        if min > round.bound(self, a).max(round.bound(self, b)) {
+            return;
+        }
+
+        if a.len() * b.len() <= LEAF_PAIRS {
+            for i in a.start..a.end {
+                for j in b.start..b.end {
+                    let (component_i, component_j) = (round.component[i], round.component[j]);
+                    if component_i == component_j || (a == b && j <= i) {
+                        continue;
+                    }
+                    let edge: (f64, usize, usize) = (
+                        distance(&self.points[i], &self.points[j]),
+                        i.min(j),
+                        i.max(j),
+                    );
+                    for component in [component_i, component_j] {
+                        if round.best[component].is_none_or(|best| edge < best) {
+                            round.best[component] = Some(edge);
+                        }
+                    }
+                }
+            }
+            round.settle(self, a);
+            round.settle(self, b);
+            return;
+        }
+
+        let mut children: Vec<(Cell, Cell)> = Vec::with_capacity(6);
+        if a == b {
+            stack.push(Task::Refresh(a));
+            let cells: [Option<Cell>; 3] = self.children(a);
+            for (k, child) in cells.iter().enumerate() {
+                if let Some(child) = child {
+                    children.push((*child, *child));
+                    children.extend(
+                        cells[k + 1..]
+                            .iter()
+                            .flatten()
+                            .map(|sibling| (*child, *sibling)),
+                    );
+                }
+            }
+        } else if a.len() >= b.len() {
+            stack.push(Task::Refresh(a));
+            children.extend(
+                self.children(a)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| (child, b)),
+            );
+        } else {
+            stack.push(Task::Refresh(b));
+            children.extend(
+                self.children(b)
+                    .into_iter()
+                    .flatten()
+                    .map(|child| (a, child)),
+            );
+        }
+
+        // Search the closest pair of cells first so the bounds shrink quickly.
+        let mut children: Vec<(Cell, Cell, f64)> = children
+            .into_iter()
+            .map(|(a, b)| {
+                (
+                    a,
+                    b,
+                    if a == b {
+                        0.
+                    } else {
+                        self.min_distance(a, self, b)
+                    },
+                )
+            })
+            .collect();
+        children.sort_by(|l, r| r.2.total_cmp(&l.2));
+        stack.extend(children.into_iter().map(|(a, b, _)| Task::Visit(a, b)));
+    }
+
+    fn push_if_within(
+        &self,
+        i: usize,
+        other: &Flat<P>,
+        j: usize,
+        radius: f64,
+        pairs: &mut Vec<(usize, usize, f64)>,
+    ) {
+        let dis: f64 = distance(&self.points[i], &other.points[j]);
+        if dis <= radius {
+            pairs.push((i, j, dis));
+        }
+    }
+
+    /// Split the tree into cells of nearby points holding at most `BATCH_SIZE` points each
+    fn batches(&self) -> Vec<Cell> {
+        let mut batches: Vec<Cell> = Vec::new();
+        let mut stack: Vec<Cell> = self.root().into_iter().collect();
+        while let Some(cell) = stack.pop() {
+            if cell.len() <= BATCH_SIZE {
+                batches.push(cell);
+            } else {
+                stack.extend(self.children(cell).into_iter().flatten());
+            }
+        }
+        batches
+    }
+
+    /// The `k` nearest points in `reference` to every point as `(index, distance)` sorted by distance
+    ///
+    /// Nearby points are searched for together so the subtrees of `reference` that are too far
+    /// from all of them are skipped once. With `exclude_self` the point with the same index in
+    /// `reference` is skipped, for finding the neighbors of the points within their own tree.
+    pub(crate) fn nearest_in(
+        &self,
+        reference: &Flat<P>,
+        k: usize,
+        exclude_self: bool,
+    ) -> Vec<Vec<(usize, f64)>> {
+        let mut nearest: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.len()];
+        for batch in self.batches() {
+            self.nearest_batch(
+                batch,
+                reference,
+                k,
+                exclude_self,
+                &mut nearest[batch.start..batch.end],
+            );
+        }
+        nearest
+    }
+
+    /// The same as `nearest_in` but spreads the batches over the threads of the rayon pool
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_nearest_in(
+        &self,
+        reference: &Flat<P>,
+        k: usize,
+        exclude_self: bool,
+    ) -> Vec<Vec<(usize, f64)>>
+    where
+        P: Sync,
+    {
+        use rayon::prelude::*;
+
+        let mut batches: Vec<Cell> = self.batches();
+        batches.sort_by_key(|batch| batch.start);
+        let mut nearest: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.len()];
+        let mut slices = Vec::with_capacity(batches.len());
+        // The batches cover the points one after the other, so each one gets the next slice.
+        let mut rest: &mut [Vec<(usize, f64)>] = &mut nearest;
+        for batch in batches {
+            let (slice, tail) = std::mem::take(&mut rest).split_at_mut(batch.len());
+            slices.push((batch, slice));
+            rest = tail;
+        }
+        slices.into_par_iter().for_each(|(batch, slice)| {
+            self.nearest_batch(batch, reference, k, exclude_self, slice)
+        });
+        nearest
+    }
+
+    fn nearest_batch(
+        &self,
+        batch: Cell,
+        reference: &Flat<P>,
+        k: usize,
+        exclude_self: bool,
+        nearest: &mut [Vec<(usize, f64)>],
+    ) {
+        let root: Cell = match reference.root() {
+            Some(root) if k > 0 => root,
+            _ => return,
+        };
+
+        // The distance a point has to beat to be a neighbor of any point in the batch.
+        let mut bound: f64 = f64::INFINITY;
+        let mut stack: Vec<(Cell, f64)> = vec![(root, self.min_distance(batch, reference, root))];
+        while let Some((cell, min)) = stack.pop() {
+            if min > bound {
+                continue;
+            }
+
+            if cell.len() > BATCH_SIZE {
+                let mut children: [Option<(Cell, f64)>; 3] =
+                    reference.children(cell).map(|child| {
+                        child.map(|child| (child, self.min_distance(batch, reference, child)))
+                    });
+                // Search the closest child first so the bound shrinks as quickly as possible.
+                children.sort_by(|a, b| match (a, b) {
+                    (Some(a), Some(b)) => b.1.total_cmp(&a.1),
+                    _ => a.is_some().cmp(&b.is_some()),
+                });
+                stack.extend(children.into_iter().flatten());
+                continue;
+            }
+
+            for (i, best) in (batch.start..batch.end).zip(nearest.iter_mut()) {
+                for j in cell.start..cell.end {
+                    if exclude_self && i == j {
+                        continue;
+                    }
+                    let dis: f64 = distance(&self.points[i], &reference.points[j]);
+                    if best.len() < k || dis < best[k - 1].1 {
+                        let index: usize = best.partition_point(|(_, other)| *other <= dis);
+                        best.insert(index, (j, dis));
+                        best.truncate(k);
+                    }
+                }
+            }
+            bound = nearest
+                .iter()
+                .map(|best| {
+                    if best.len() < k {
+                        f64::INFINITY
+                    } else {
+                        best[k - 1].1
+                    }
+                })
+                .fold(0., f64::max);
+        }
+    }
+}
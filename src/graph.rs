@@ -0,0 +1,100 @@
+//! Neighbor graphs over every point in a tree.
+
+use crate::flat::Flat;
+use crate::neighbor::Neighbor;
+use crate::point::KdPoint;
+use crate::{KdNode, KDT};
+
+use std::collections::HashSet;
+
+/// How [`knn_graph_symmetric`](../enum.KdNode.html#method.knn_graph_symmetric) makes the
+/// nearest neighbor relation symmetric.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Keep an edge only when both points are among each other's nearest neighbors
+    Mutual,
+    /// Keep an edge when either point is among the other's nearest neighbors
+    Union,
+}
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find the `k` nearest other points of every point in the tree
+    ///
+    /// Every point of the tree is returned in the order of [`iter`](#method.iter) along with
+    /// its `k` nearest neighbors sorted by distance, the point itself is never its own neighbor.
+    /// Nearby points are searched for together, so this is much faster than calling
+    /// [`n_nearest_neighbor`](#method.n_nearest_neighbor) for every point.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![
+    ///     Point { x: 0., y: 0. },
+    ///     Point { x: 1., y: 0. },
+    ///     Point { x: 3., y: 0. },
+    /// ]);
+    /// for (point, neighbors) in node.knn_graph(1) {
+    ///     let expected = if point.x == 3. { 2. } else { 1. };
+    ///     assert_eq!(neighbors[0].distance, expected);
+    /// }
+    /// ```
+    pub fn knn_graph(&self, k: usize) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let flat: Flat<P> = Flat::new(self);
+        let nearest = flat.nearest_in(&flat, k, true);
+        KdNode::join_lists(&flat, &flat, nearest)
+    }
+
+    /// Find the `k` nearest other points of every point and make the graph symmetric
+    ///
+    /// The same as [`knn_graph`](#method.knn_graph) except that `b` is a neighbor of `a` exactly
+    /// when `a` is a neighbor of `b`. With [`Symmetry::Mutual`](graph/enum.Symmetry.html) points
+    /// can be left with fewer than `k` neighbors, with `Symmetry::Union` they can have more.
+    pub fn knn_graph_symmetric(&self, k: usize, symmetry: Symmetry) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let flat: Flat<P> = Flat::new(self);
+        let nearest = flat.nearest_in(&flat, k, true);
+        KdNode::join_lists(&flat, &flat, Self::symmetric(nearest, symmetry))
+    }
+
+    /// Make the `k` nearest neighbors of every point symmetric
+    fn symmetric(nearest: Vec<Vec<(usize, f64)>>, symmetry: Symmetry) -> Vec<Vec<(usize, f64)>> {
+        let edges: HashSet<(usize, usize)> = nearest
+            .iter()
+            .enumerate()
+            .flat_map(|(i, neighbors)| neighbors.iter().map(move |(j, _)| (i, *j)))
+            .collect();
+
+        let mut symmetric: Vec<Vec<(usize, f64)>> = vec![Vec::new(); nearest.len()];
+        for (i, neighbors) in nearest.iter().enumerate() {
+            for (j, dis) in neighbors {
+                match symmetry {
+                    Symmetry::Mutual if edges.contains(&(*j, i)) => symmetric[i].push((*j, *dis)),
+                    Symmetry::Mutual => {}
+                    Symmetry::Union => {
+                        symmetric[i].push((*j, *dis));
+                        if !edges.contains(&(*j, i)) {
+                            symmetric[*j].push((i, *dis));
+                        }
+                    }
+                }
+            }
+        }
+        for neighbors in symmetric.iter_mut() {
+            neighbors.sort_by(|a, b| a.1.total_cmp(&b.1));
+        }
+        symmetric
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Send + Sync,
+    P: KdPoint<Scalar = T> + Send + Sync,
+{
+    /// The same as [`knn_graph`](#method.knn_graph) but spread over the rayon thread pool
+    pub fn par_knn_graph(&self, k: usize) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let flat: Flat<P> = Flat::new(self);
+        let nearest = flat.par_nearest_in(&flat, k, true);
+        KdNode::join_lists(&flat, &flat, nearest)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::point::{KdPoint, Point};
+use crate::KDT;
+
+use ::geo_types::{Coord, CoordNum};
+
+impl<T: KDT + CoordNum> KdPoint for ::geo_types::Point<T> {
+    type Scalar = T;
+    const DIMENSIONS: usize = 2;
+
+    fn get(&self, dim: usize) -> T {
+        self.0.get(dim)
+    }
+}
+
+impl<T: KDT + CoordNum> KdPoint for Coord<T> {
+    type Scalar = T;
+    const DIMENSIONS: usize = 2;
+
+    fn get(&self, dim: usize) -> T {
+        match dim {
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("Coord only has two dimensions"),
+        }
+    }
+}
+
+impl<T: KDT + CoordNum> From<::geo_types::Point<T>> for Point<T> {
+    fn from(value: ::geo_types::Point<T>) -> Self {
+        Point::from(value.0)
+    }
+}
+
+impl<T: KDT + CoordNum> From<Point<T>> for ::geo_types::Point<T> {
+    fn from(value: Point<T>) -> Self {
+        ::geo_types::Point::new(value.x, value.y)
+    }
+}
+
+impl<T: KDT + CoordNum> From<Coord<T>> for Point<T> {
+    fn from(value: Coord<T>) -> Self {
+        Point {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl<T: KDT + CoordNum> From<Point<T>> for Coord<T> {
+    fn from(value: Point<T>) -> Self {
+        Coord {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+#[cfg(test)]
+fn brute_force(
+    stations: &[::geo_types::Point<f64>],
+    origin: ::geo_types::Point<f64>,
+    radius: f64,
+) -> Vec<::geo_types::Point<f64>> {
+    use crate::metric::{Haversine, Metric};
+
+    stations
+        .iter()
+        .filter(|station| Haversine::EARTH.distance(&origin, *station) <= radius)
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+fn sorted(mut points: Vec<::geo_types::Point<f64>>) -> Vec<::geo_types::Point<f64>> {
+    points.sort_by(|a, b| (a.x(), a.y()).partial_cmp(&(b.x(), b.y())).unwrap());
+    points
+}
+
+#[test]
+fn test_geo_haversine_radius() {
+    use crate::metric::Haversine;
+    use crate::KdNode;
+
+    // A grid of stations roughly 1km apart straddling the antimeridian at 60 degrees north
+    // and a second grid near the equator.
+    let mut stations: Vec<::geo_types::Point<f64>> = Vec::new();
+    for i in -40..40 {
+        for j in -10..10 {
+            let lon: f64 = 179.9 + i as f64 * 0.0125;
+            let lon: f64 = if lon > 180. { lon - 360. } else { lon };
+            stations.push(::geo_types::Point::new(lon, 60. + j as f64 * 0.009));
+            stations.push(::geo_types::Point::new(i as f64 * 0.009, j as f64 * 0.009));
+        }
+    }
+    let node: KdNode<f64, ::geo_types::Point<f64>> = KdNode::build(stations.clone());
+
+    let origins = [
+        ::geo_types::Point::new(179.999, 60.),
+        ::geo_types::Point::new(-179.999, 60.02),
+        ::geo_types::Point::new(179.85, 59.95),
+        ::geo_types::Point::new(0.01, 0.),
+    ];
+    for origin in origins {
+        let expected = brute_force(&stations, origin, 5_000.);
+        let found = node.nearest_neighbor_metric(origin, 5_000., &Haversine::EARTH);
+        assert!(!expected.is_empty());
+        assert_eq!(sorted(found), sorted(expected));
+    }
+
+    // Both sides of the antimeridian are found
+    let found = node.nearest_neighbor_metric(
+        ::geo_types::Point::new(180., 60.),
+        5_000.,
+        &Haversine::EARTH,
+    );
+    assert!(found.iter().any(|station| station.x() > 179.));
+    assert!(found.iter().any(|station| station.x() < -179.));
+}
+
+#[test]
+fn test_geo_conversions() {
+    assert_eq!(
+        Point::from(::geo_types::Point::new(1., 2.)),
+        Point { x: 1., y: 2. }
+    );
+    assert_eq!(
+        ::geo_types::Point::from(Point { x: 1, y: 2 }),
+        ::geo_types::Point::new(1, 2)
+    );
+    assert_eq!(Coord::from(Point { x: 3., y: 4. }), Coord { x: 3., y: 4. });
+}
@@ -0,0 +1,93 @@
+use crate::point::{KdPoint, Point};
+
+macro_rules! impl_glam {
+    ($($vec:ty => $scalar:ty, $dimensions:expr;)*) => {
+        $(
+            impl KdPoint for $vec {
+                type Scalar = $scalar;
+                const DIMENSIONS: usize = $dimensions;
+
+                fn get(&self, dim: usize) -> $scalar {
+                    self[dim]
+                }
+            }
+        )*
+    };
+}
+
+impl_glam! {
+    ::glam::Vec2 => f32, 2;
+    ::glam::Vec3 => f32, 3;
+    ::glam::Vec3A => f32, 3;
+    ::glam::Vec4 => f32, 4;
+    ::glam::DVec2 => f64, 2;
+    ::glam::DVec3 => f64, 3;
+    ::glam::DVec4 => f64, 4;
+    ::glam::IVec2 => i32, 2;
+    ::glam::IVec3 => i32, 3;
+    ::glam::IVec4 => i32, 4;
+    ::glam::UVec2 => u32, 2;
+    ::glam::UVec3 => u32, 3;
+    ::glam::UVec4 => u32, 4;
+}
+
+macro_rules! impl_glam_from {
+    ($($vec:ty => $scalar:ty;)*) => {
+        $(
+            impl From<$vec> for Point<$scalar> {
+                fn from(value: $vec) -> Self {
+                    Point { x: value.x, y: value.y }
+                }
+            }
+
+            impl From<Point<$scalar>> for $vec {
+                fn from(value: Point<$scalar>) -> Self {
+                    <$vec>::new(value.x, value.y)
+                }
+            }
+        )*
+    };
+}
+
+impl_glam_from! {
+    ::glam::Vec2 => f32;
+    ::glam::DVec2 => f64;
+    ::glam::IVec2 => i32;
+    ::glam::UVec2 => u32;
+}
+
+#[test]
+fn test_glam_vec3() {
+    use crate::KdNode;
+    use ::glam::Vec3;
+
+    let points: Vec<Vec3> = vec![
+        Vec3::new(1., 8., 0.),
+        Vec3::new(2., 2., 1.),
+        Vec3::new(3., 6., 2.),
+        Vec3::new(4., 9., 3.),
+        Vec3::new(7., 3., 4.),
+        Vec3::new(8., 8., 5.),
+        Vec3::new(9., 1., 6.),
+        Vec3::new(9., 9., 7.),
+    ];
+    let node: KdNode<f32, Vec3> = KdNode::build(points);
+
+    assert_eq!(
+        node.nearest_neighbor(Vec3::new(8.5, 8.5, 6.), 1.5),
+        vec![Vec3::new(8., 8., 5.), Vec3::new(9., 9., 7.)]
+    );
+    assert_eq!(node.nearest_neighbor(Vec3::new(8.5, 8.5, 0.), 1.5), vec![]);
+}
+
+#[test]
+fn test_glam_conversions() {
+    use ::glam::{IVec2, Vec2};
+
+    assert_eq!(Point::from(Vec2::new(1., 2.)), Point { x: 1., y: 2. });
+    assert_eq!(Vec2::from(Point { x: 1., y: 2. }), Vec2::new(1., 2.));
+    assert_eq!(
+        IVec2::from(Point::from(IVec2::new(-1, 2))),
+        IVec2::new(-1, 2)
+    );
+}
@@ -0,0 +1,90 @@
+use crate::point::{KdPoint, Point};
+use crate::KDT;
+
+macro_rules! impl_mint {
+    ($($mint:ident, $dimensions:expr, [$($field:ident),*];)*) => {
+        $(
+            impl<T: KDT> KdPoint for ::mint::$mint<T> {
+                type Scalar = T;
+                const DIMENSIONS: usize = $dimensions;
+
+                fn get(&self, dim: usize) -> T {
+                    [$(self.$field),*][dim]
+                }
+            }
+        )*
+    };
+}
+
+impl_mint! {
+    Point2, 2, [x, y];
+    Point3, 3, [x, y, z];
+    Vector2, 2, [x, y];
+    Vector3, 3, [x, y, z];
+    Vector4, 4, [x, y, z, w];
+}
+
+macro_rules! impl_mint_from {
+    ($($mint:ident;)*) => {
+        $(
+            impl<T: KDT> From<::mint::$mint<T>> for Point<T> {
+                fn from(value: ::mint::$mint<T>) -> Self {
+                    Point { x: value.x, y: value.y }
+                }
+            }
+
+            impl<T: KDT> From<Point<T>> for ::mint::$mint<T> {
+                fn from(value: Point<T>) -> Self {
+                    ::mint::$mint { x: value.x, y: value.y }
+                }
+            }
+        )*
+    };
+}
+
+impl_mint_from! {
+    Point2;
+    Vector2;
+}
+
+#[test]
+fn test_mint_point3() {
+    use crate::KdNode;
+    use ::mint::Point3;
+
+    let points: Vec<Point3<f32>> = (0..10)
+        .map(|i| Point3 {
+            x: i as f32,
+            y: 0.,
+            z: -(i as f32),
+        })
+        .collect();
+    let node: KdNode<f32, Point3<f32>> = KdNode::build(points);
+
+    assert_eq!(
+        node.nearest_neighbor(
+            Point3 {
+                x: 4.1,
+                y: 0.,
+                z: -4.
+            },
+            0.5
+        ),
+        vec![Point3 {
+            x: 4.,
+            y: 0.,
+            z: -4.
+        }]
+    );
+}
+
+#[test]
+fn test_mint_conversions() {
+    use ::mint::{Point2, Vector2};
+
+    assert_eq!(Point::from(Point2 { x: 1, y: 2 }), Point { x: 1, y: 2 });
+    assert_eq!(
+        Vector2::from(Point { x: 1., y: 2. }),
+        Vector2 { x: 1., y: 2. }
+    );
+}
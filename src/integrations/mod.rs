@@ -0,0 +1,13 @@
+//! [`KdPoint`](../point/trait.KdPoint.html) implementations for the point types of other crates.
+//!
+//! Each integration is behind a cargo feature of the same name as the crate,
+//! `geo` enables the integration with `geo-types`.
+
+#[cfg(feature = "geo")]
+mod geo;
+#[cfg(feature = "glam")]
+mod glam;
+#[cfg(feature = "mint")]
+mod mint;
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
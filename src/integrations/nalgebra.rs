@@ -0,0 +1,82 @@
+use crate::point::{KdPoint, Point};
+use crate::KDT;
+
+use ::nalgebra::{Point2, SVector, Scalar, Vector2};
+
+impl<T: KDT + Scalar, const D: usize> KdPoint for ::nalgebra::Point<T, D> {
+    type Scalar = T;
+    const DIMENSIONS: usize = D;
+
+    fn get(&self, dim: usize) -> T {
+        self[dim]
+    }
+}
+
+impl<T: KDT + Scalar, const D: usize> KdPoint for SVector<T, D> {
+    type Scalar = T;
+    const DIMENSIONS: usize = D;
+
+    fn get(&self, dim: usize) -> T {
+        self[dim]
+    }
+}
+
+impl<T: KDT + Scalar> From<Point2<T>> for Point<T> {
+    fn from(value: Point2<T>) -> Self {
+        Point {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl<T: KDT + Scalar> From<Point<T>> for Point2<T> {
+    fn from(value: Point<T>) -> Self {
+        Point2::new(value.x, value.y)
+    }
+}
+
+impl<T: KDT + Scalar> From<Vector2<T>> for Point<T> {
+    fn from(value: Vector2<T>) -> Self {
+        Point {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl<T: KDT + Scalar> From<Point<T>> for Vector2<T> {
+    fn from(value: Point<T>) -> Self {
+        Vector2::new(value.x, value.y)
+    }
+}
+
+#[test]
+fn test_nalgebra_point3() {
+    use crate::KdNode;
+    use ::nalgebra::Point3;
+
+    let points: Vec<Point3<f64>> = (0..10)
+        .map(|i| Point3::new(i as f64, (i * i) as f64, 1.))
+        .collect();
+    let node: KdNode<f64, Point3<f64>> = KdNode::build(points);
+
+    assert_eq!(
+        node.nearest_neighbor(Point3::new(3., 9., 1.5), 1.),
+        vec![Point3::new(3., 9., 1.)]
+    );
+    assert_eq!(
+        node.n_nearest_neighbor(Point3::new(0., 0., 0.), 1),
+        vec![Point3::new(0., 0., 1.)]
+    );
+}
+
+#[test]
+fn test_nalgebra_conversions() {
+    assert_eq!(Point::from(Point2::new(1, 2)), Point { x: 1, y: 2 });
+    assert_eq!(Point2::from(Point { x: 1., y: 2. }), Point2::new(1., 2.));
+    assert_eq!(
+        Vector2::from(Point::from(Vector2::new(3., 4.))),
+        Vector2::new(3., 4.)
+    );
+}
@@ -0,0 +1,200 @@
+//! Estimating values between points that carry measurements.
+
+use crate::neighbor::Neighbor;
+pub use crate::neighbor::Neighbors;
+use crate::point::{Item, KdPoint};
+use crate::search::SearchScratch;
+use crate::{KdNode, KDT};
+
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// How [`evaluate`](enum.Interpolation.html#method.evaluate) estimates a value from the points
+/// around the origin.
+///
+/// The tree holds each measurement as the data of an [`Item`](../point/struct.Item.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    /// The mean of the values weighted by one over their distance to the power of `power`
+    ///
+    /// A point at the origin has an infinite weight, its value is used on its own.
+    InverseDistance { power: f64, neighbors: Neighbors },
+    /// The mean of the values of the `k` nearest points
+    Mean { k: usize },
+    /// The median of the values of the `k` nearest points, the mean of the middle two when `k` is even
+    Median { k: usize },
+}
+
+/// Points spaced evenly along every axis, the first axis changes fastest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// The coordinates of the first point
+    pub lower: Vec<f64>,
+    /// The space between points along every axis
+    pub step: Vec<f64>,
+    /// The number of points along every axis
+    pub shape: Vec<usize>,
+}
+
+impl Grid {
+    /// The number of points in the grid
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The coordinates of the point at index `i`
+    pub fn coordinates(&self, i: usize) -> Vec<f64> {
+        let mut rest: usize = i;
+        self.shape
+            .iter()
+            .enumerate()
+            .map(|(dim, count)| {
+                let step: usize = rest % count;
+                rest /= count;
+                self.lower[dim] + step as f64 * self.step[dim]
+            })
+            .collect()
+    }
+}
+
+impl Interpolation {
+    /// Estimate the value at `origin` from the points of the tree
+    ///
+    /// Returns `None` when there are no points to estimate from, either because the tree is
+    /// empty or none are within the distance given by [`Neighbors::Within`].
+    ///
+    /// ```rust
+    /// use kd_tree_rs::interpolate::{Interpolation, Neighbors};
+    /// use kd_tree_rs::point::Item;
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node = KdNode::build(vec![Item::new(Point { x: 0., y: 0. }, 10.), Item::new(Point { x: 4., y: 0. }, 20.)]);
+    /// let idw = Interpolation::InverseDistance { power: 2., neighbors: Neighbors::Nearest(2) };
+    /// assert!((idw.evaluate(&node, Point { x: 1., y: 0. }).unwrap() - 11.).abs() < 1e-9);
+    /// assert_eq!(Interpolation::Mean { k: 2 }.evaluate(&node, Point { x: 1., y: 0. }), Some(15.));
+    /// ```
+    pub fn evaluate<T, P>(&self, tree: &KdNode<T, Item<P, f64>>, origin: P) -> Option<f64>
+    where
+        T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+        P: KdPoint<Scalar = T>,
+    {
+        self.evaluate_all(tree, [origin]).pop().flatten()
+    }
+
+    /// Estimate the value at each of the origins, in order
+    ///
+    /// The same as [`evaluate`](#method.evaluate) for every origin but reusing the buffers of
+    /// the searches. Nearby origins are faster one after the other.
+    pub fn evaluate_all<T, P>(
+        &self,
+        tree: &KdNode<T, Item<P, f64>>,
+        origins: impl IntoIterator<Item = P>,
+    ) -> Vec<Option<f64>>
+    where
+        T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+        P: KdPoint<Scalar = T>,
+    {
+        let mut near: Vec<Neighbor<Item<P, f64>>> = Vec::new();
+        let mut scratch: SearchScratch<T, Item<P, f64>> = SearchScratch::new();
+        let mut values: Vec<f64> = Vec::new();
+        origins
+            .into_iter()
+            .map(|origin| {
+                let origin: Item<P, f64> = Item::new(origin, 0.);
+                match self {
+                    Interpolation::InverseDistance {
+                        neighbors: Neighbors::Within(radius),
+                        ..
+                    } => tree.nearest_neighbor_into(origin, *radius, &mut near, &mut scratch),
+                    Interpolation::InverseDistance {
+                        neighbors: Neighbors::Nearest(k),
+                        ..
+                    }
+                    | Interpolation::Mean { k }
+                    | Interpolation::Median { k } => {
+                        tree.n_nearest_neighbor_into(origin, *k, &mut near, &mut scratch)
+                    }
+                }
+                if near.is_empty() {
+                    return None;
+                }
+
+                Some(match self {
+                    Interpolation::InverseDistance { power, .. } => inverse_distance(&near, *power),
+                    Interpolation::Mean { .. } => {
+                        near.iter().map(|neighbor| neighbor.point.data).sum::<f64>()
+                            / near.len() as f64
+                    }
+                    Interpolation::Median { .. } => {
+                        values.clear();
+                        values.extend(near.iter().map(|neighbor| neighbor.point.data));
+                        values.sort_by(f64::total_cmp);
+                        let middle: usize = values.len() / 2;
+                        if values.len() % 2 == 1 {
+                            values[middle]
+                        } else {
+                            (values[middle - 1] + values[middle]) / 2.
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Estimate the value at every point of the grid, in the order of the grid
+    ///
+    /// `point` turns the coordinates of a grid point into a point of the tree.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::interpolate::{Grid, Interpolation};
+    /// use kd_tree_rs::point::Item;
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node = KdNode::build(vec![Item::new(Point { x: 0., y: 0. }, 1.), Item::new(Point { x: 10., y: 0. }, 2.)]);
+    /// let grid = Grid { lower: vec![0., 0.], step: vec![5., 5.], shape: vec![3, 1] };
+    /// let values = Interpolation::Mean { k: 1 }.evaluate_grid(&node, &grid, |c| Point { x: c[0], y: c[1] });
+    /// assert_eq!(values, vec![Some(1.), Some(1.), Some(2.)]);
+    /// ```
+    pub fn evaluate_grid<T, P, F>(
+        &self,
+        tree: &KdNode<T, Item<P, f64>>,
+        grid: &Grid,
+        point: F,
+    ) -> Vec<Option<f64>>
+    where
+        T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+        P: KdPoint<Scalar = T>,
+        F: Fn(&[f64]) -> P,
+    {
+        assert_eq!(
+            grid.shape.len(),
+            P::DIMENSIONS,
+            "The grid must have the dimensions of the points"
+        );
+        self.evaluate_all(tree, (0..grid.len()).map(|i| point(&grid.coordinates(i))))
+    }
+}
+
+fn inverse_distance<P: KdPoint>(near: &[Neighbor<Item<P, f64>>], power: f64) -> f64 {
+    // Points at the origin would divide by zero, they decide the value on their own.
+    let exact: Vec<f64> = near
+        .iter()
+        .filter(|neighbor| neighbor.distance == 0.)
+        .map(|neighbor| neighbor.point.data)
+        .collect();
+    if !exact.is_empty() {
+        return exact.iter().sum::<f64>() / exact.len() as f64;
+    }
+
+    let (mut weighted, mut total) = (0., 0.);
+    for neighbor in near {
+        let weight: f64 = neighbor.distance.powf(-power);
+        weighted += weight * neighbor.point.data;
+        total += weight;
+    }
+    weighted / total
+}
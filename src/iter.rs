@@ -0,0 +1,55 @@
+use crate::point::KdPoint;
+use crate::{KdNode, Node, KDT};
+
+/// Iterator over the points of a tree, see [`KdNode::iter`](../enum.KdNode.html#method.iter).
+///
+/// Points are visited in pre-order, each point comes before the points of its left
+/// subtree which come before those of its right subtree.
+#[derive(Debug)]
+pub struct Iter<'a, T: KDT, P: KdPoint<Scalar = T>> {
+    stack: Vec<&'a KdNode<T, P>>,
+    remaining: usize,
+}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> Iter<'a, T, P> {
+    pub(crate) fn new(node: &'a KdNode<T, P>) -> Self {
+        Iter {
+            stack: vec![node],
+            remaining: node.len(),
+        }
+    }
+}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> Iterator for Iter<'a, T, P> {
+    type Item = &'a P;
+
+    fn next(&mut self) -> Option<&'a P> {
+        while let Some(node) = self.stack.pop() {
+            if let Node {
+                point, left, right, ..
+            } = node
+            {
+                self.stack.push(right);
+                self.stack.push(left);
+                self.remaining -= 1;
+                return Some(point);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> ExactSizeIterator for Iter<'a, T, P> {}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> IntoIterator for &'a KdNode<T, P> {
+    type Item = &'a P;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Iter<'a, T, P> {
+        Iter::new(self)
+    }
+}
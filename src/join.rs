@@ -0,0 +1,108 @@
+use crate::flat::Flat;
+use crate::neighbor::Neighbor;
+use crate::point::{squared_distance, KdPoint};
+use crate::{KdNode, KDT};
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find the `k` nearest points in `reference` to every point in this tree
+    ///
+    /// Every point of this tree is returned in the order of [`iter`](#method.iter) along with
+    /// its `k` nearest points of `reference` sorted by distance. Nearby points of this tree are
+    /// searched for together, skipping the subtrees of `reference` whose bounding box is too far
+    /// from all of them.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let tracks: KdNode<f64> = KdNode::build(vec![Point { x: 0., y: 0. }, Point { x: 10., y: 0. }]);
+    /// let detections: KdNode<f64> = KdNode::build(vec![Point { x: 9., y: 1. }]);
+    ///
+    /// let matches = detections.knn_join(&tracks, 1);
+    /// assert_eq!(matches[0].1[0].point, Point { x: 10., y: 0. });
+    /// ```
+    pub fn knn_join(&self, reference: &KdNode<T, P>, k: usize) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let queries: Flat<P> = Flat::new(self);
+        let reference: Flat<P> = Flat::new(reference);
+        let nearest = queries.nearest_in(&reference, k, false);
+        KdNode::join_lists(&queries, &reference, nearest)
+    }
+
+    /// Find every pair of a point in this tree and a point in `other` within `radius` of each other
+    ///
+    /// Pairs are returned in no particular order as the point of this tree, the point of `other`
+    /// and the distance between them. Both trees are walked together so pairs of subtrees that
+    /// are too far apart are skipped without looking at their points.
+    pub fn radius_join(&self, other: &KdNode<T, P>, radius: f64) -> Vec<(P, P, f64)> {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let other: Flat<P> = Flat::new(other);
+        let pairs = flat.pairs_between(&other, radius);
+        pairs
+            .into_iter()
+            .map(|(i, j, dis)| (flat.points[i], other.points[j], dis))
+            .collect()
+    }
+
+    /// Find the closest pair of a point in this tree and a point in `other`
+    ///
+    /// Returns the point of this tree, the point of `other` and the distance between them,
+    /// or `None` when either tree is empty.
+    pub fn closest_pair_between(&self, other: &KdNode<T, P>) -> Option<(P, P, f64)> {
+        let flat: Flat<P> = Flat::new(self);
+        let other: Flat<P> = Flat::new(other);
+        let (i, j, dis) = flat.closest_between(&other)?;
+        Some((flat.points[i], other.points[j], dis))
+    }
+
+    /// Pair every query point with its neighbors given by their index in `reference`
+    pub(crate) fn join_lists(
+        queries: &Flat<P>,
+        reference: &Flat<P>,
+        nearest: Vec<Vec<(usize, f64)>>,
+    ) -> Vec<(P, Vec<Neighbor<P>>)> {
+        queries
+            .points
+            .iter()
+            .zip(nearest)
+            .map(|(point, neighbors)| {
+                let neighbors = neighbors
+                    .into_iter()
+                    .map(|(j, _)| {
+                        let squared: f64 = squared_distance(point, &reference.points[j]);
+                        Neighbor::new(reference.points[j], squared)
+                    })
+                    .collect();
+                (*point, neighbors)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Send + Sync,
+    P: KdPoint<Scalar = T> + Send + Sync,
+{
+    /// The same as [`knn_join`](#method.knn_join) but spread over the rayon thread pool
+    pub fn par_knn_join(&self, reference: &KdNode<T, P>, k: usize) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let queries: Flat<P> = Flat::new(self);
+        let reference: Flat<P> = Flat::new(reference);
+        let nearest = queries.par_nearest_in(&reference, k, false);
+        KdNode::join_lists(&queries, &reference, nearest)
+    }
+
+    /// The same as [`radius_join`](#method.radius_join) but spread over the rayon thread pool
+    pub fn par_radius_join(&self, other: &KdNode<T, P>, radius: f64) -> Vec<(P, P, f64)> {
+        use rayon::prelude::*;
+
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let other: Flat<P> = Flat::new(other);
+        let pairs = flat.par_pairs_between(&other, radius);
+        pairs
+            .into_par_iter()
+            .map(|(i, j, dis)| (flat.points[i], other.points[j], dis))
+            .collect()
+    }
+}
@@ -12,11 +12,11 @@
 //! * [x] Find Nearest Neighbor
 //! * [x] Insert New Point
 //! * [x] Find **N** Nearest Neighbors
-//! * [ ] Delete Point
-//! * [ ] Re-Balance Tree
+//! * [x] Delete Point
+//! * [x] Re-Balance Tree
 //! * [ ] Serialize Tree
 //! * [ ] Publish Crate
-//! * [ ] Add **K** dimensions **(Currently only 2D)**
+//! * [x] Add **K** dimensions
 //! * [x] Add Examples
 //!
 //! This was developed initially as a way to learn Rust and to implement a KD Tree for a boids simulation although the
@@ -28,7 +28,19 @@
 //!
 //! [`Point`](point/struct.Point.html) which is a struct that contains the x and y coordinates of a point in 2D space.
 //!
-//! The type of the x and y coordinates can be any type that can implement the [`KDT`](trait.KDT.html) trait.
+//! Any type implementing [`KdPoint`](point/trait.KdPoint.html) can be stored in the tree instead of
+//! [`Point`](point/struct.Point.html), so your own point types with any number of dimensions can be used directly.
+//!
+//! Implementations for the point and vector types of [`glam`](https://crates.io/crates/glam),
+//! [`nalgebra`](https://crates.io/crates/nalgebra) and [`mint`](https://crates.io/crates/mint) are available
+//! behind the cargo features of the same name, and `geo` adds support for `geo_types::Point`.
+//! The `rayon` feature adds `par_` variants of the queries that look at every point in the tree.
+//!
+//! Queries use the euclidean distance by default, the `_metric` variants take any
+//! [`Metric`](metric/trait.Metric.html) such as [`Haversine`](metric/struct.Haversine.html)
+//! for great-circle distances between longitude / latitude points.
+//!
+//! The type of the coordinates can be any type that can implement the [`KDT`](trait.KDT.html) trait.
 //! This trait is implemented for all types that implement the following traits:
 //! [`PartialEq`](https://doc.rust-lang.org/std/cmp/trait.PartialEq.html),
 //! [`PartialOrd`](https://doc.rust-lang.org/std/cmp/trait.PartialOrd.html),
@@ -64,17 +76,43 @@
 //! * [Proof for neighborhood computation in expected logarithmic time - Martin Skrodzki](https://arxiv.org/pdf/1903.04936.pdf)
 //! * [Introduction to a KD Tree](https://yasenh.github.io/post/kd-tree/)
 
-
 extern crate core;
 
+pub mod bounds;
+pub mod cloud;
+pub mod cluster;
+mod count;
+pub mod density;
 pub mod dim;
+mod farthest;
+mod flat;
+pub mod graph;
+mod integrations;
+pub mod interpolate;
+pub mod iter;
+mod join;
+pub mod metric;
+pub mod neighbor;
+mod pairs;
 pub mod point;
+mod ray;
+pub mod region;
+mod reverse;
+pub mod sample;
+pub mod search;
 mod tests;
+mod walk;
 
+pub use crate::bounds::Bounds;
 pub use crate::dim::Dim;
-pub use crate::point::Point;
+pub use crate::graph::Symmetry;
+pub use crate::iter::Iter;
+pub use crate::metric::{Euclidean, Haversine, Metric};
+pub use crate::neighbor::Neighbor;
+use crate::point::AxisCmp;
+pub use crate::point::{Indexed, Item, KdPoint, Point};
+pub use crate::search::SearchScratch;
 pub use crate::KdNode::{Empty, Node};
-use crate::point::distance;
 use std::cmp::Ordering;
 use std::ops::{Add, Mul, Sub};
 
@@ -90,25 +128,118 @@ impl<T> KDT for T where
 {
 }
 
-#[derive(Debug, PartialEq)]
-pub enum KdNode<T: KDT> {
+#[derive(Debug, PartialEq, Default)]
+pub enum KdNode<T: KDT, P: KdPoint<Scalar = T> = Point<T>> {
+    #[default]
     Empty,
     Node {
-        point: Point<T>,
+        point: P,
         dim: Dim,
-        left: Box<KdNode<T>>,
-        right: Box<KdNode<T>>,
+        left: Box<KdNode<T, P>>,
+        right: Box<KdNode<T, P>>,
+        /// The number of points in this subtree, including `point`
+        size: usize,
+        /// The bounding box of this subtree, only stored by trees built with bounds
+        bounds: Option<Bounds>,
     },
 }
 
-impl<T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::Debug> Default for KdNode<T> {
-    fn default() -> Self {
-        Self::new()
+/// How unbalanced a subtree can become before it is rebuilt.
+///
+/// A subtree is rebuilt once one of its children holds more than this share of its points.
+const BALANCE: f64 = 0.75;
+
+/// Drop the tree without recursing so deep trees do not overflow the stack.
+impl<T: KDT, P: KdPoint<Scalar = T>> Drop for KdNode<T, P> {
+    fn drop(&mut self) {
+        let mut stack: Vec<KdNode<T, P>> = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut node) = stack.pop() {
+            // With its children taken the node drops without going any deeper.
+            node.take_children(&mut stack);
+        }
     }
 }
 
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Check if the tree has no points
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Empty)
+    }
+
+    /// Count the points in the tree
+    pub fn len(&self) -> usize {
+        match self {
+            Empty => 0,
+            Node { size, .. } => *size,
+        }
+    }
+
+    /// The bounding box of the tree, `None` when it is empty or was built without bounds
+    ///
+    /// Trees grown from empty with [`insert_point`](#method.insert_point) store bounds.
+    pub fn bounds(&self) -> Option<&Bounds> {
+        match self {
+            Node { bounds, .. } => bounds.as_ref(),
+            Empty => None,
+        }
+    }
+
+    /// Iterate over the points in the tree
+    ///
+    /// Points are visited in pre-order, the queries that return one entry for every
+    /// point in the tree, such as [`radius_graph`](#method.radius_graph), use the same order.
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter::new(self)
+    }
+
+    /// Collect the points in the tree
+    fn points(&self) -> Vec<P> {
+        self.iter().copied().collect()
+    }
+
+    /// Move the children of the node onto the stack, leaving it with empty children.
+    fn take_children(&mut self, stack: &mut Vec<KdNode<T, P>>) {
+        if let Node { left, right, .. } = self {
+            for child in [left, right] {
+                if !matches!(child.as_ref(), Empty) {
+                    stack.push(std::mem::replace(child.as_mut(), Empty));
+                }
+            }
+        }
+    }
+}
 
 impl<T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::Debug> KdNode<T> {
+    /// Insert a new item into the tree
+    ///
+    /// Subtrees that become lopsided are rebuilt as points are inserted, see
+    /// [`insert_point`](#method.insert_point). When most of the dataset changes at
+    /// once it is still cheaper to build a new tree.
+    pub fn insert(&mut self, x: T, y: T) -> &Self {
+        self.insert_point(Point { x, y })
+    }
+
+    /// Remove a point at the `x` and `y` coordinates from the tree
+    ///
+    /// This is the same as `remove_point` but takes `x` and `y` instead of a `Point`
+    pub fn remove(&mut self, x: T, y: T) -> bool {
+        self.remove_point(Point { x, y })
+    }
+
+    /// Find the nearest neighbors to the `x` and `y` coordinates
+    ///
+    /// This is the same as `nearest_neighbor` but takes `x` and `y` instead of a `Point`
+    pub fn nearest_neighbor_x_y(&self, x: T, y: T, radius: f64) -> Vec<Point<T>> {
+        self.nearest_neighbor(Point { x, y }, radius)
+    }
+}
+
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::Debug,
+    P: KdPoint<Scalar = T>,
+{
     /// Create a new empty tree
     pub fn new() -> Self {
         Empty
@@ -116,46 +247,221 @@ impl<T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::De
 
     /// Insert a new item into the tree
     ///
-    /// This should used sparingly as it can unbalance the tree
-    /// and reduce performance. If there is a large change to the dataset
-    /// it is better to create a new tree. This effect has not been tested
-    /// though and could be totally fine in terms of performance for a large
-    /// number of inserts. A good rule of thumb may be if the tree size is
-    /// going to increase by more than 10% it may be better to create a new
-    /// tree.
-    pub fn insert(&mut self, x: T, y: T) -> &Self {
-        self.insert_point(Point{ x, y })
+    /// This is the same as `insert` but takes a point instead of `x` and `y`
+    ///
+    /// If one side of a subtree ends up holding most of its points the subtree is
+    /// rebuilt, so inserting sorted points does not degrade the tree into a list.
+    pub fn insert_point(&mut self, item: P) -> &Self {
+        if let Some(depth) = self._insert(item) {
+            self.rebuild_at(&item, depth);
+        }
+        self
     }
 
-    /// Insert a new item into the tree
+    /// Insert the item as a new leaf
     ///
-    /// This is the same as `insert` but takes a `Point` instead of `x` and `y`
-    pub fn insert_point(&mut self, item: Point<T>) -> &Self {
-        self._insert(item, 0)
+    /// Returns the depth of the highest subtree on the way down that became unbalanced.
+    fn _insert(&mut self, item: P) -> Option<usize> {
+        let mut depth: usize = 0;
+        let mut unbalanced: Option<usize> = None;
+        // An empty tree does not know how it was built, so a first point starts a bounded tree.
+        let mut bounded: bool = true;
+        let mut node: &mut KdNode<T, P> = self;
+        while let Node {
+            point,
+            dim,
+            left,
+            right,
+            size,
+            bounds,
+        } = node
+        {
+            *size += 1;
+            bounded = bounds.is_some();
+            if let Some(bounds) = bounds {
+                bounds.extend(&item);
+            }
+            let child: &mut KdNode<T, P> = if point.gt(&item, dim) { right } else { left };
+            if unbalanced.is_none() && (child.len() + 1) as f64 > BALANCE * *size as f64 {
+                unbalanced = Some(depth);
+            }
+
+            node = child;
+            depth += 1;
+        }
+
+        *node = Node {
+            point: item,
+            dim: Dim::from_depth(depth, P::DIMENSIONS),
+            left: Box::new(Empty),
+            right: Box::new(Empty),
+            size: 1,
+            bounds: bounded.then(|| Bounds::of([&item])),
+        };
+        unbalanced
     }
 
-    fn _insert(&mut self, item: Point<T>, depth: usize) -> &Self {
-        *self = match self {
-            Empty => Node {
-                point: item,
-                dim: Dim::from_depth(depth),
-                left: Box::new(Empty),
-                right: Box::new(Empty),
-            },
-            Node {
-                point, left, right, ..
-            } => {
-                let next_depth: usize = depth + 1;
-                if point.gt(&item, &Dim::from_depth(next_depth)) {
-                    right._insert(item, next_depth);
+    /// Rebuild the subtree `depth` levels down the path `item` takes through the tree
+    fn rebuild_at(&mut self, item: &P, depth: usize) {
+        let mut node: &mut KdNode<T, P> = self;
+        for _ in 0..depth {
+            node = match node {
+                Node {
+                    point,
+                    dim,
+                    left,
+                    right,
+                    ..
+                } => {
+                    if point.gt(item, dim) {
+                        right
+                    } else {
+                        left
+                    }
+                }
+                Empty => unreachable!("Rebuild path ended early"),
+            };
+        }
+        let points: Vec<P> = node.points();
+        let bounded: bool = node.bounds().is_some();
+        *node = KdNode::_build(points, depth, bounded);
+    }
+
+    /// Remove a point with the same coordinates as `item` from the tree
+    ///
+    /// Returns `false` when the tree holds no such point. The subtree below the removed point is
+    /// rebuilt without it, so removing points high up in the tree is slower than removing leaves.
+    /// As with inserting, a subtree left holding most of its points on one side is rebuilt too.
+    pub fn remove_point(&mut self, item: P) -> bool {
+        let path: Vec<bool> = match self.find_path(&item) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        // Rebuild the highest subtree on the path that becomes unbalanced, or else the one below
+        // the removed point. `index` tracks where the removed point is in the subtree's points.
+        let mut depth: usize = 0;
+        let mut index: usize = 0;
+        let mut node: &KdNode<T, P> = self;
+        for went_left in &path {
+            if let Node {
+                left, right, size, ..
+            } = node
+            {
+                let (child, other) = if *went_left {
+                    (left, right)
                 } else {
-                    left._insert(item, next_depth);
+                    (right, left)
+                };
+                if other.len() as f64 > BALANCE * (size - 1) as f64 {
+                    break;
                 }
-                return self;
+                depth += 1;
+                node = child;
             }
-        };
+        }
+        for went_left in &path[depth..] {
+            if let Node { left, right, .. } = node {
+                index += if *went_left { 1 } else { 1 + left.len() };
+                node = if *went_left { left } else { right };
+            }
+        }
 
-        self
+        let mut node: &mut KdNode<T, P> = self;
+        for went_left in &path[..depth] {
+            node = match node {
+                Node {
+                    left, right, size, ..
+                } => {
+                    *size -= 1;
+                    if *went_left {
+                        left
+                    } else {
+                        right
+                    }
+                }
+                Empty => unreachable!("Remove path ended early"),
+            };
+        }
+        let mut points: Vec<P> = node.points();
+        points.remove(index);
+        let bounded: bool = node.bounds().is_some();
+        *node = KdNode::_build(points, depth, bounded);
+
+        // The boxes above the rebuilt subtree may have shrunk, refresh them from the bottom up.
+        if bounded {
+            for level in (0..depth).rev() {
+                let mut node: &mut KdNode<T, P> = self;
+                for went_left in &path[..level] {
+                    node = match node {
+                        Node { left, right, .. } => {
+                            if *went_left {
+                                left
+                            } else {
+                                right
+                            }
+                        }
+                        Empty => unreachable!("Remove path ended early"),
+                    };
+                }
+                node.refresh_bounds();
+            }
+        }
+        true
+    }
+
+    /// Find the path to a node with the same coordinates as `item`, `true` for every step left
+    fn find_path(&self, item: &P) -> Option<Vec<bool>> {
+        let mut path: Vec<bool> = Vec::new();
+        let mut stack: Vec<(&KdNode<T, P>, usize, bool)> = vec![(self, 0, false)];
+        while let Some((node, depth, went_left)) = stack.pop() {
+            if let Node {
+                point,
+                dim,
+                left,
+                right,
+                ..
+            } = node
+            {
+                if depth > 0 {
+                    path.truncate(depth - 1);
+                    path.push(went_left);
+                }
+                if (0..P::DIMENSIONS).all(|dim| point.get(dim) == item.get(dim)) {
+                    return Some(path);
+                }
+
+                // Points equal to the split can be on either side of it.
+                match point.cmp(item, dim) {
+                    Ordering::Less => stack.push((left, depth + 1, true)),
+                    Ordering::Greater => stack.push((right, depth + 1, false)),
+                    Ordering::Equal => {
+                        stack.push((right, depth + 1, false));
+                        stack.push((left, depth + 1, true));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Recompute the box of the node from its point and the boxes of its children
+    fn refresh_bounds(&mut self) {
+        if let Node {
+            point,
+            left,
+            right,
+            bounds: Some(bounds),
+            ..
+        } = self
+        {
+            *bounds = Bounds::of([&*point]);
+            for child in [left, right] {
+                if let Some(child_bounds) = child.bounds() {
+                    bounds.merge(child_bounds);
+                }
+            }
+        }
     }
 
     /// Find the nearest neighbors to the origin point
@@ -163,136 +469,288 @@ impl<T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::De
     /// This will return a vector of points that are within the radius of the origin point.
     /// The radius is inclusive so if a point is exactly on the radius it will be included.
     ///
-    pub fn nearest_neighbor(&self, origin: Point<T>, radius: f64) -> Vec<Point<T>> {
-        assert!(radius >= 0.0, "Radius must be positive");
-
-        let mut best_queue: Vec<(&KdNode<T>, f64)> = Vec::new();
-        let mut parent_queue: Vec<&KdNode<T>> = self.drill_down(origin);
-        let deepest: &KdNode<T> = parent_queue.get(0).unwrap();
-
-        deepest._nearest_neighbor(origin, radius, &mut best_queue, &mut parent_queue, None);
+    pub fn nearest_neighbor(&self, origin: P, radius: f64) -> Vec<P> {
+        self.nearest_neighbor_metric(origin, radius, &Euclidean)
+    }
 
-        best_queue.retain(|(_, dist)| *dist <= radius);
-        return best_queue
-            .iter()
-            .map(|(node, _)| match node {
-                Node { point, .. } => point.clone(),
-                _ => panic!("Empty node in best queue"),
-            })
-            .collect();
+    /// Find the nearest neighbors to the origin point using the given metric
+    ///
+    /// This is the same as `nearest_neighbor` but measures the radius with `metric`,
+    /// for example [`Haversine`](metric/struct.Haversine.html) for longitude / latitude points.
+    pub fn nearest_neighbor_metric<M: Metric<P>>(
+        &self,
+        origin: P,
+        radius: f64,
+        metric: &M,
+    ) -> Vec<P> {
+        Neighbor::points(self.nearest_neighbor_metric_with_distance(origin, radius, metric))
     }
 
-    /// Insert a new item into the tree
+    /// Find the nearest neighbors to the origin point along with their distances
     ///
-    /// This is the same as `insert` but takes a `Point` instead of `x` and `y`
-    pub fn nearest_neighbor_x_y(&self, x: T, y: T, radius: f64) -> Vec<Point<T>> {
-        self.nearest_neighbor(Point { x, y }, radius)
+    /// This is the same as `nearest_neighbor` but keeps the distance to each point,
+    /// the neighbors are sorted from closest to furthest.
+    pub fn nearest_neighbor_with_distance(&self, origin: P, radius: f64) -> Vec<Neighbor<P>> {
+        self.nearest_neighbor_metric_with_distance(origin, radius, &Euclidean)
+    }
+
+    /// Find the nearest neighbors to the origin point along with their distances using the given metric
+    pub fn nearest_neighbor_metric_with_distance<M: Metric<P>>(
+        &self,
+        origin: P,
+        radius: f64,
+        metric: &M,
+    ) -> Vec<Neighbor<P>> {
+        assert!(radius >= 0.0, "Radius must be positive");
+        self.search(origin, radius, metric, None)
     }
 
     /// Find the nearest neighbors to the origin point
     ///
     /// This will return a vector of points that are within the radius of the origin point.
     /// This is the same as `nearest_neighbor` but will only return the `max` number of points.
-    pub fn n_nearest_neighbor(&self, origin: Point<T>, max: usize) -> Vec<Point<T>> {
-        let mut best_queue: Vec<(&KdNode<T>, f64)> = Vec::new();
-        let mut parent_queue: Vec<&KdNode<T>> = self.drill_down(origin);
-        let deepest: &KdNode<T> = parent_queue.get(0).unwrap();
-
-        // TODO Should use just an option instead of `f64::MAX`.
-        deepest._nearest_neighbor(origin, f64::MAX, &mut best_queue, &mut parent_queue, Some(max));
-
-        return best_queue
-            .iter()
-            .map(|(node, _)| match node {
-                Node { point, .. } => point.clone(),
+    pub fn n_nearest_neighbor(&self, origin: P, max: usize) -> Vec<P> {
+        self.n_nearest_neighbor_metric(origin, max, &Euclidean)
+    }
+
+    /// Find the `max` nearest neighbors to the origin point using the given metric
+    pub fn n_nearest_neighbor_metric<M: Metric<P>>(
+        &self,
+        origin: P,
+        max: usize,
+        metric: &M,
+    ) -> Vec<P> {
+        Neighbor::points(self.n_nearest_neighbor_metric_with_distance(origin, max, metric))
+    }
+
+    /// Find the `max` nearest neighbors to the origin point along with their distances
+    ///
+    /// The neighbors are sorted from closest to furthest.
+    pub fn n_nearest_neighbor_with_distance(&self, origin: P, max: usize) -> Vec<Neighbor<P>> {
+        self.n_nearest_neighbor_metric_with_distance(origin, max, &Euclidean)
+    }
+
+    /// Find the `max` nearest neighbors to the origin point along with their distances using the given metric
+    pub fn n_nearest_neighbor_metric_with_distance<M: Metric<P>>(
+        &self,
+        origin: P,
+        max: usize,
+        metric: &M,
+    ) -> Vec<Neighbor<P>> {
+        if max == 0 {
+            return Vec::new();
+        }
+        self.search(origin, f64::INFINITY, metric, Some(max))
+    }
+
+    /// Find the nearest neighbors to the origin point without allocating
+    ///
+    /// This is the same as `nearest_neighbor_with_distance` but writes the neighbors into `out`,
+    /// replacing its contents, and reuses the buffers in `scratch`. Once `out` and `scratch` have
+    /// grown large enough repeated queries do not allocate.
+    pub fn nearest_neighbor_into<'a>(
+        &'a self,
+        origin: P,
+        radius: f64,
+        out: &mut Vec<Neighbor<P>>,
+        scratch: &mut SearchScratch<'a, T, P>,
+    ) {
+        self.nearest_neighbor_metric_into(origin, radius, &Euclidean, out, scratch)
+    }
+
+    /// Find the nearest neighbors to the origin point using the given metric without allocating
+    pub fn nearest_neighbor_metric_into<'a, M: Metric<P>>(
+        &'a self,
+        origin: P,
+        radius: f64,
+        metric: &M,
+        out: &mut Vec<Neighbor<P>>,
+        scratch: &mut SearchScratch<'a, T, P>,
+    ) {
+        assert!(radius >= 0.0, "Radius must be positive");
+        self.search_into(origin, radius, metric, None, out, scratch)
+    }
+
+    /// Find the `max` nearest neighbors to the origin point without allocating
+    ///
+    /// This is the same as `n_nearest_neighbor_with_distance` but writes the neighbors into `out`,
+    /// replacing its contents, and reuses the buffers in `scratch`.
+    pub fn n_nearest_neighbor_into<'a>(
+        &'a self,
+        origin: P,
+        max: usize,
+        out: &mut Vec<Neighbor<P>>,
+        scratch: &mut SearchScratch<'a, T, P>,
+    ) {
+        self.n_nearest_neighbor_metric_into(origin, max, &Euclidean, out, scratch)
+    }
+
+    /// Find the `max` nearest neighbors to the origin point using the given metric without allocating
+    pub fn n_nearest_neighbor_metric_into<'a, M: Metric<P>>(
+        &'a self,
+        origin: P,
+        max: usize,
+        metric: &M,
+        out: &mut Vec<Neighbor<P>>,
+        scratch: &mut SearchScratch<'a, T, P>,
+    ) {
+        if max == 0 {
+            out.clear();
+            return;
+        }
+        self.search_into(origin, f64::INFINITY, metric, Some(max), out, scratch)
+    }
+
+    fn search<M: Metric<P>>(
+        &self,
+        origin: P,
+        radius: f64,
+        metric: &M,
+        max: Option<usize>,
+    ) -> Vec<Neighbor<P>> {
+        let mut out: Vec<Neighbor<P>> = Vec::new();
+        self.search_into(
+            origin,
+            radius,
+            metric,
+            max,
+            &mut out,
+            &mut SearchScratch::new(),
+        );
+        out
+    }
+
+    fn search_into<'a, M: Metric<P>>(
+        &'a self,
+        origin: P,
+        radius: f64,
+        metric: &M,
+        max: Option<usize>,
+        out: &mut Vec<Neighbor<P>>,
+        scratch: &mut SearchScratch<'a, T, P>,
+    ) {
+        let SearchScratch {
+            best_queue,
+            parent_queue,
+        } = scratch;
+        best_queue.clear();
+        self.drill_down(origin, parent_queue);
+
+        KdNode::_nearest_neighbor(origin, radius, metric, best_queue, parent_queue, max);
+
+        out.clear();
+        out.extend(best_queue.iter().filter(|(_, dist)| *dist <= radius).map(
+            |(node, _)| match node {
+                Node { point, .. } => {
+                    Neighbor::new(*point, metric.squared_distance(&origin, point))
+                }
                 _ => panic!("Empty node in best queue"),
-            })
-            .collect();
+            },
+        ));
+        best_queue.clear();
     }
 
     /// Find the nearest neighbors to the origin point
     ///
-    /// This is a recursive function that will recursively work its way up the tree
-    /// collecting all neighbours within the radius provided.
-    fn _nearest_neighbor<'a>(
-        &'a self,
-        origin: Point<T>,
+    /// This works its way up the tree from the bottom of the drill down path in `parent_queue`,
+    /// searching the other side of each parent when it may hold neighbours within the radius.
+    fn _nearest_neighbor<'a, M: Metric<P>>(
+        origin: P,
         radius: f64,
-        best_queue: &mut Vec<(&'a KdNode<T>, f64)>,
-        parent_queue: &mut Vec<&'a KdNode<T>>,
+        metric: &M,
+        best_queue: &mut Vec<(&'a KdNode<T, P>, f64)>,
+        parent_queue: &mut Vec<&'a KdNode<T, P>>,
         max: Option<usize>,
-    ) -> Vec<(&KdNode<T>, f64)> {
-        let parent = parent_queue.pop();
-        if parent.is_none() {
-            return best_queue.clone();
-        }
+    ) {
+        // The bottom of the queue holds the drill down path, its nodes are popped from
+        // the deepest up and the child they lead to has already been searched.
+        let mut path_len: usize = parent_queue.len();
+        let mut previous: Option<&'a KdNode<T, P>> = None;
+
+        while let Some(parent) = parent_queue.pop() {
+            let searched: Option<&'a KdNode<T, P>> = if parent_queue.len() < path_len {
+                path_len = parent_queue.len();
+                previous.replace(parent)
+            } else {
+                None
+            };
 
-        match parent.unwrap() {
-            Empty => {}
-            Node {
+            if let Node {
                 left,
                 right,
                 point,
+                dim,
                 ..
-            } => {
-                if let Some(max) = max {
-                    if best_queue.len() >= max {
-                        return vec![];
-                    }
-                }
-
+            } = parent
+            {
                 // Add node point if in range.
-                let dis = distance(&origin, point);
-                if dis <= radius {
-                    KdNode::insert_sorted(best_queue, (parent.unwrap(), distance(&origin, point)));
+                let dis = metric.distance(&origin, point);
+                if dis <= KdNode::bound(best_queue, radius, max) {
+                    KdNode::insert_sorted(best_queue, (parent, dis));
+                    KdNode::truncate(best_queue, max);
                 }
 
-                for side_node in [left, right] {
-                    if !best_queue.iter().any(|(a, _)| *a == side_node.as_ref())
+                let split: f64 = point.get_dim_value(dim).into();
+                for (side_node, min, max_split) in
+                    [(left, split, f64::INFINITY), (right, f64::NEG_INFINITY, split)]
+                {
+                    // Nodes are told apart by address, every node is visited at most once.
+                    if side_node.is_empty()
+                        || searched
+                            .is_some_and(|searched| std::ptr::eq(searched, side_node.as_ref()))
                     {
-                        // Check if the radius actually overlaps the node children.
-                        if let Node { point, dim, .. } = side_node.as_ref() {
-                            if !point.in_radius(&origin, dim, radius) {
-                                continue;
-                            }
-                        }
+                        continue;
+                    }
 
-                        parent_queue.push(side_node.as_ref());
-                        let temp =
-                            side_node._nearest_neighbor(origin, radius, best_queue, parent_queue, max);
-                        for (node, dist) in temp {
-                            if dist <= radius {
-                                if let Some(max) = max {
-                                    if best_queue.len() >= max {
-                                        return vec![];
-                                    }
-                                }
-                                KdNode::insert_sorted(best_queue, (node, dist));
-                            }
+                    // Check if the radius actually overlaps the side of the split, or the child's box
+                    // when it stores one.
+                    let reach: f64 = match side_node.bounds() {
+                        Some(bounds) => {
+                            metric.box_distance(&origin, bounds.lower(), bounds.upper())
                         }
+                        None => metric.axis_distance(&origin, dim, min, max_split),
+                    };
+                    if reach > KdNode::bound(best_queue, radius, max) {
+                        continue;
                     }
-                }
 
-                parent.unwrap()._nearest_neighbor(origin, radius, best_queue, parent_queue, max);
+                    parent_queue.push(side_node.as_ref());
+                }
             }
         }
+    }
 
-        best_queue.clone()
+    /// The furthest distance a point can be to still be a neighbor.
+    ///
+    /// Once `max` neighbors have been found this shrinks to the distance of the furthest one.
+    fn bound(best_queue: &[(&KdNode<T, P>, f64)], radius: f64, max: Option<usize>) -> f64 {
+        match max {
+            Some(max) if best_queue.len() >= max => best_queue[max - 1].1.min(radius),
+            _ => radius,
+        }
+    }
+
+    /// Drop the furthest neighbors beyond the `max` closest.
+    fn truncate(best_queue: &mut Vec<(&KdNode<T, P>, f64)>, max: Option<usize>) {
+        if let Some(max) = max {
+            best_queue.truncate(max);
+        }
     }
 
-    /// Drill down the tree to find appropriate node and return the parents.
-    fn drill_down(&self, origin: Point<T>) -> Vec<&KdNode<T>> {
-        let mut parents: Vec<&KdNode<T>> = Vec::new();
-        let mut best_node: &KdNode<T> = self;
+    /// Drill down the tree to find appropriate node and fill `parents` with the path to it.
+    fn drill_down<'a>(&'a self, origin: P, parents: &mut Vec<&'a KdNode<T, P>>) {
+        parents.clear();
+        let mut best_node: &KdNode<T, P> = self;
         while let Node {
             point,
             left,
             right,
             dim,
+            ..
         } = best_node
         {
             parents.push(best_node);
-            if *left == Box::new(Empty) && *right == Box::new(Empty) {
+            if left.is_empty() && right.is_empty() {
                 break;
             }
 
@@ -301,78 +759,117 @@ impl<T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + std::fmt::De
                 _ => best_node = right,
             }
         }
-        parents
     }
 
-    /// Insert a point into a sorted list if it is not already in the list.
+    /// Insert a point into a list sorted by distance, after any closer points.
     fn insert_sorted<'a>(
-        points: &mut Vec<(&'a KdNode<T>, f64)>,
-        point: (&'a KdNode<T>, f64),
+        points: &mut Vec<(&'a KdNode<T, P>, f64)>,
+        point: (&'a KdNode<T, P>, f64),
     ) {
-        let mut index: usize = 0;
-        for (i, (node, dist)) in points.iter().enumerate() {
-            if *dist < point.1 {
-                index = i + 1;
-            }
-            if *node == point.0 && *node != &Empty {
-                return;
-            }
-        }
+        let index: usize = points.partition_point(|(_, dist)| *dist < point.1);
         points.insert(index, point);
     }
 
-    pub fn build(points: Vec<Point<T>>) -> Self {
-        KdNode::_build(points, 1)
+    pub fn build(points: Vec<P>) -> Self {
+        KdNode::_build(points, 0, false)
+    }
+
+    /// Build a tree that stores the bounding box of every subtree
+    ///
+    /// Each node holds a box of `2 * DIMENSIONS` coordinates. In return queries skip the
+    /// subtrees whose box is out of reach instead of only looking at the coordinate they were
+    /// split on. Points inserted or removed later keep the boxes up to date.
+    ///
+    /// An empty tree stores nothing, so this is the same as [`new`](#method.new) for no points.
+    /// Inserting into any empty tree starts a tree with bounds.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build_with_bounds(vec![Point { x: 0., y: 3. }, Point { x: 2., y: 1. }]);
+    /// let bounds = node.bounds().unwrap();
+    /// assert_eq!(bounds.lower(), [0., 1.]);
+    /// assert_eq!(bounds.upper(), [2., 3.]);
+    /// ```
+    pub fn build_with_bounds(points: Vec<P>) -> Self {
+        KdNode::_build(points, 0, true)
+    }
+
+    /// Build a tree that remembers where each point was in `points`
+    ///
+    /// Every point is stored as an [`Indexed`](point/type.Indexed.html) point holding its
+    /// position in `points`, so the results of queries can be matched back up with the input.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{Item, KdNode, Point};
+    ///
+    /// let node = KdNode::build_indexed(vec![Point { x: 0., y: 0. }, Point { x: 5., y: 5. }]);
+    /// let nearest = node.n_nearest_neighbor_with_distance(Item::new(Point { x: 4., y: 4. }, 0), 1);
+    /// assert_eq!(nearest[0].point.data, 1);
+    /// ```
+    pub fn build_indexed(points: Vec<P>) -> KdNode<T, Indexed<P>> {
+        KdNode::build(
+            points
+                .into_iter()
+                .enumerate()
+                .map(|(index, point)| Item::new(point, index))
+                .collect(),
+        )
     }
 
-    fn _build(points: Vec<Point<T>>, depth: usize) -> Self {
-        // Increment the dimension
-        let next_depth: usize = depth + 1;
+    fn _build(points: Vec<P>, depth: usize, bounded: bool) -> Self {
+        let mut root: KdNode<T, P> = Empty;
+        let mut stack: Vec<(&mut KdNode<T, P>, Vec<P>, usize)> = vec![(&mut root, points, depth)];
 
-        // End recursion if there are one or no points
-        if points.is_empty() {
-            return Empty;
-        } else if points.len() == 1 {
-            return Node {
-                point: points[0],
-                dim: Dim::from_depth(next_depth),
+        while let Some((node, points, depth)) = stack.pop() {
+            // Nothing to add if there are no points
+            if points.is_empty() {
+                continue;
+            }
+
+            // Choose axis
+            let axis = Dim::from_depth(depth, P::DIMENSIONS);
+
+            let bounds: Option<Bounds> = bounded.then(|| Bounds::of(&points));
+
+            // Get Median
+            let (median, left, right): (P, Vec<P>, Vec<P>) = KdNode::split_on_median(points, &axis);
+
+            *node = Node {
+                point: median,
+                dim: axis,
                 left: Box::new(Empty),
                 right: Box::new(Empty),
+                size: left.len() + right.len() + 1,
+                bounds,
             };
-        }
-
-        // Choose axis
-        let axis = Dim::from_depth(next_depth);
 
-        // Get Median
-        let (median, left, right): (Point<T>, Vec<Point<T>>, Vec<Point<T>>) =
-            KdNode::split_on_median(points, &axis);
-
-        Node {
-            point: median,
-            dim: axis,
-            left: Box::from(Self::_build(left, next_depth)),
-            right: Box::from(Self::_build(right, next_depth)),
+            if let Node {
+                left: left_node,
+                right: right_node,
+                ..
+            } = node
+            {
+                // Increment the dimension
+                let next_depth: usize = depth + 1;
+                stack.push((left_node, left, next_depth));
+                stack.push((right_node, right, next_depth));
+            }
         }
+
+        root
     }
 
     /// Split the points into two vectors based on the median
     ///
     /// The median is chosen based on the axis and returned along with
     /// two separate vectors of points, the left and right of the median.
-    fn split_on_median(
-        mut points: Vec<Point<T>>,
-        axis: &Dim,
-    ) -> (Point<T>, Vec<Point<T>>, Vec<Point<T>>) {
-        points.sort_by(|a: &Point<T>, b: &Point<T>| a.cmp(b, axis));
-        let median_index: usize = if points.len() % 2 == 0 {
-            points.len() / 2 - 1
-        } else {
-            points.len() / 2
-        };
-        let median: Point<T> = points[median_index];
-        let right: Vec<Point<T>> = points.drain(..median_index).collect();
-        let left: Vec<Point<T>> = points.drain(1..).collect();
+    fn split_on_median(mut points: Vec<P>, axis: &Dim) -> (P, Vec<P>, Vec<P>) {
+        points.sort_by(|a: &P, b: &P| a.cmp(b, axis));
+        let median_index: usize = (points.len() - 1) / 2;
+        let median: P = points[median_index];
+        let right: Vec<P> = points.drain(..median_index).collect();
+        let left: Vec<P> = points.drain(1..).collect();
         (median, left, right)
     }
 }
@@ -0,0 +1,238 @@
+//! Distance metrics used when searching the tree.
+//!
+//! [`Euclidean`](struct.Euclidean.html) is used by default, the `_metric` variants of the
+//! queries accept any [`Metric`](trait.Metric.html) such as [`Haversine`](struct.Haversine.html)
+//! for longitude / latitude points.
+
+use crate::dim::Dim;
+use crate::point::{distance, squared_distance, KdPoint};
+use crate::walk::min_distance;
+
+use std::f64::consts::FRAC_PI_2;
+
+/// A distance between two points along with a lower bound used to prune subtrees.
+pub trait Metric<P: KdPoint> {
+    /// Distance between two points
+    fn distance(&self, ls: &P, rs: &P) -> f64;
+
+    /// Square of the distance between two points
+    fn squared_distance(&self, ls: &P, rs: &P) -> f64 {
+        let distance: f64 = self.distance(ls, rs);
+        distance * distance
+    }
+
+    /// Lower bound of the distance from `origin` to any point with a coordinate
+    /// along `dim` between `min` and `max` (inclusive).
+    ///
+    /// `min` and `max` may be infinite when the region is only bounded on one side.
+    fn axis_distance(&self, origin: &P, dim: &Dim, min: f64, max: f64) -> f64;
+
+    /// Lower bound of the distance from `origin` to any point in the box between `lower` and `upper`
+    ///
+    /// Defaults to the largest of the bounds along each axis.
+    fn box_distance(&self, origin: &P, lower: &[f64], upper: &[f64]) -> f64 {
+        (0..P::DIMENSIONS)
+            .map(|dim| self.axis_distance(origin, &Dim::new(dim), lower[dim], upper[dim]))
+            .fold(0., f64::max)
+    }
+}
+
+/// Straight line distance between points.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Euclidean;
+
+impl<P: KdPoint> Metric<P> for Euclidean {
+    fn distance(&self, ls: &P, rs: &P) -> f64 {
+        distance(ls, rs)
+    }
+
+    fn squared_distance(&self, ls: &P, rs: &P) -> f64 {
+        squared_distance(ls, rs)
+    }
+
+    fn axis_distance(&self, origin: &P, dim: &Dim, min: f64, max: f64) -> f64 {
+        let value: f64 = origin.get(dim.index()).into();
+        (min - value).max(value - max).max(0.)
+    }
+
+    fn box_distance(&self, origin: &P, lower: &[f64], upper: &[f64]) -> f64 {
+        min_distance(origin, lower, upper)
+    }
+}
+
+/// Great-circle distance between longitude / latitude points.
+///
+/// Points are expected to store the longitude in degrees as their first coordinate
+/// and the latitude in degrees as their second, the same as `geo_types::Point`.
+/// Distances are returned in the same unit as `radius`. Measuring points that do not
+/// have exactly two dimensions panics.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Haversine {
+    pub radius: f64,
+}
+
+impl Haversine {
+    /// Mean radius of the earth in meters.
+    pub const EARTH: Haversine = Haversine {
+        radius: 6_371_008.8,
+    };
+
+    pub fn new(radius: f64) -> Self {
+        Haversine { radius }
+    }
+
+    /// Angle between `(lon, lat)` and the half meridian at `meridian`, all in degrees.
+    fn meridian_angle(lon: f64, lat: f64, meridian: f64) -> f64 {
+        let delta: f64 = wrap_longitude(lon - meridian).abs().to_radians();
+        if delta <= FRAC_PI_2 {
+            (delta.sin() * lat.to_radians().cos()).min(1.).asin()
+        } else {
+            // The closest point of the half meridian is the nearest pole.
+            FRAC_PI_2 - lat.to_radians().abs()
+        }
+    }
+}
+
+impl Default for Haversine {
+    fn default() -> Self {
+        Haversine::EARTH
+    }
+}
+
+impl<P: KdPoint> Metric<P> for Haversine {
+    fn distance(&self, ls: &P, rs: &P) -> f64 {
+        let (lon1, lat1) = lon_lat(ls);
+        let (lon2, lat2) = lon_lat(rs);
+
+        let d_lat: f64 = (lat2 - lat1).to_radians();
+        let d_lon: f64 = (lon2 - lon1).to_radians();
+        let a: f64 = (d_lat / 2.).sin().powi(2)
+            + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.).sin().powi(2);
+        2. * self.radius * a.sqrt().min(1.).asin()
+    }
+
+    fn axis_distance(&self, origin: &P, dim: &Dim, min: f64, max: f64) -> f64 {
+        let (lon, lat) = lon_lat(origin);
+        let angle: f64 = match dim.index() {
+            // Every point in a band of latitudes is at least the latitude difference away.
+            1 => (min - lat).max(lat - max).max(0.).to_radians(),
+            // A band of longitudes is a wedge between two half meridians, the
+            // shortest path to it may cross the antimeridian.
+            _ => {
+                if (min <= -180. && max >= 180.) || (min <= lon && lon <= max) {
+                    0.
+                } else {
+                    let min: f64 = min.max(-180.);
+                    let max: f64 = max.min(180.);
+                    Haversine::meridian_angle(lon, lat, min)
+                        .min(Haversine::meridian_angle(lon, lat, max))
+                }
+            }
+        };
+        angle * self.radius
+    }
+}
+
+fn lon_lat<P: KdPoint>(point: &P) -> (f64, f64) {
+    assert_eq!(
+        P::DIMENSIONS,
+        2,
+        "Haversine requires longitude / latitude points"
+    );
+    (point.get(0).into(), point.get(1).into())
+}
+
+/// Wrap a longitude difference in degrees into `[-180, 180]`
+fn wrap_longitude(degrees: f64) -> f64 {
+    (degrees + 180.).rem_euclid(360.) - 180.
+}
+
+#[test]
+fn test_haversine_distance() {
+    use crate::point::Point;
+
+    let london = Point {
+        x: -0.1278,
+        y: 51.5074,
+    };
+    let paris = Point {
+        x: 2.3522,
+        y: 48.8566,
+    };
+    let distance: f64 = Haversine::EARTH.distance(&london, &paris);
+    assert!((distance - 343_560.).abs() < 500., "{}", distance);
+
+    // One degree of longitude across the antimeridian at the equator
+    let east = Point { x: 179.5, y: 0. };
+    let west = Point { x: -179.5, y: 0. };
+    let expected: f64 = 1f64.to_radians() * Haversine::EARTH.radius;
+    assert!((Haversine::EARTH.distance(&east, &west) - expected).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "Haversine requires longitude / latitude points")]
+fn test_haversine_rejects_three_dimensions() {
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    struct Position([f64; 3]);
+
+    impl KdPoint for Position {
+        type Scalar = f64;
+        const DIMENSIONS: usize = 3;
+
+        fn get(&self, dim: usize) -> f64 {
+            self.0[dim]
+        }
+    }
+
+    Haversine::EARTH.distance(&Position([0., 0., 0.]), &Position([1., 1., 1.]));
+}
+
+#[test]
+fn test_haversine_axis_distance_is_lower_bound() {
+    use crate::point::Point;
+
+    let metric = Haversine::EARTH;
+    let origins = [
+        Point { x: 179.9, y: 64. },
+        Point { x: -179.9, y: -45. },
+        Point { x: 0., y: 0. },
+        Point { x: 90., y: 89. },
+    ];
+    let bands: [(f64, f64); 5] = [
+        (-180., -170.),
+        (170., 180.),
+        (-10., 10.),
+        (f64::NEG_INFINITY, -100.),
+        (100., f64::INFINITY),
+    ];
+
+    for origin in origins {
+        for (min, max) in bands {
+            let lon_bound: f64 = metric.axis_distance(&origin, &Dim::X, min, max);
+            let lat_bound: f64 = metric.axis_distance(&origin, &Dim::Y, min / 2., max / 2.);
+            for step in 0..=100 {
+                let t: f64 = step as f64 / 100.;
+                for other in -18..=18 {
+                    let lon: f64 = min.max(-180.) + t * (max.min(180.) - min.max(-180.));
+                    let point = Point {
+                        x: lon,
+                        y: other as f64 * 5.,
+                    };
+                    assert!(lon_bound <= metric.distance(&origin, &point) + 1e-6);
+
+                    let lat: f64 =
+                        (min / 2.).max(-90.) + t * ((max / 2.).min(90.) - (min / 2.).max(-90.));
+                    let point = Point {
+                        x: other as f64 * 10.,
+                        y: lat,
+                    };
+                    assert!(lat_bound <= metric.distance(&origin, &point) + 1e-6);
+                }
+            }
+        }
+    }
+
+    // The closest part of the band is just across the antimeridian.
+    let bound: f64 = metric.axis_distance(&Point { x: -179.99, y: 0. }, &Dim::X, 179.98, 180.);
+    assert!(bound < 2_000.);
+}
@@ -0,0 +1,40 @@
+/// A point found by a query along with its distance from the origin.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Neighbor<P> {
+    pub point: P,
+    pub distance: f64,
+    pub squared_distance: f64,
+}
+
+impl<P> Neighbor<P> {
+    /// A point at `squared_distance` from the origin, the distance is its square root
+    pub fn new(point: P, squared_distance: f64) -> Self {
+        Neighbor {
+            point,
+            distance: squared_distance.sqrt(),
+            squared_distance,
+        }
+    }
+
+    pub(crate) fn points(neighbors: Vec<Neighbor<P>>) -> Vec<P> {
+        neighbors
+            .into_iter()
+            .map(|neighbor| neighbor.point)
+            .collect()
+    }
+}
+
+impl<P> From<Neighbor<P>> for (P, f64) {
+    fn from(neighbor: Neighbor<P>) -> Self {
+        (neighbor.point, neighbor.distance)
+    }
+}
+
+/// The points around an origin used to estimate something about it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Neighbors {
+    /// The given number of nearest points
+    Nearest(usize),
+    /// Every point within the given distance
+    Within(f64),
+}
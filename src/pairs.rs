@@ -0,0 +1,138 @@
+use crate::flat::Flat;
+use crate::neighbor::Neighbor;
+use crate::point::{squared_distance, KdPoint};
+use crate::{KdNode, KDT};
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find every pair of points within `radius` of each other
+    ///
+    /// Each unordered pair is returned once, in no particular order, along with the distance
+    /// between the points. Rather than searching around every point the tree is walked against
+    /// itself, so subtrees that are too far apart are skipped without looking at their points.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![
+    ///     Point { x: 0., y: 0. },
+    ///     Point { x: 1., y: 0. },
+    ///     Point { x: 5., y: 0. },
+    /// ]);
+    /// let pairs = node.neighbor_pairs(2.);
+    /// assert_eq!(pairs.len(), 1);
+    /// assert_eq!(pairs[0].2, 1.);
+    /// ```
+    pub fn neighbor_pairs(&self, radius: f64) -> Vec<(P, P, f64)> {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let pairs = flat.pairs_within(radius);
+        pairs
+            .into_iter()
+            .map(|(i, j, dis)| (flat.points[i], flat.points[j], dis))
+            .collect()
+    }
+
+    /// Find the two closest points in the tree
+    ///
+    /// Returns the pair of distinct points with the smallest distance between them along with
+    /// that distance, or `None` when the tree holds fewer than two points. Points stored twice
+    /// are distinct points at a distance of zero.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 5, y: 5 }, Point { x: 6, y: 5 }]);
+    /// let (_, _, distance) = node.closest_pair().unwrap();
+    /// assert_eq!(distance, 1.);
+    /// ```
+    pub fn closest_pair(&self) -> Option<(P, P, f64)> {
+        let flat: Flat<P> = Flat::new(self);
+        let (i, j, dis) = flat.closest_between(&flat)?;
+        Some((flat.points[i], flat.points[j], dis))
+    }
+
+    /// Find the euclidean minimum spanning tree of the points
+    ///
+    /// Returns the edges of the tree, the shortest way of joining every point with straight
+    /// lines, as pairs of points along with the distance between them. There is one edge fewer
+    /// than points and they are sorted from shortest to longest.
+    ///
+    /// The edges are found with a dual-tree Borůvka search. Each round looks for the shortest
+    /// edge leaving every group of joined points by walking the tree against itself, skipping
+    /// pairs of subtrees already joined or too far apart to beat the edges found so far.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 3, y: 0 }, Point { x: 3, y: 4 }]);
+    /// let edges = node.emst();
+    /// assert_eq!(edges.iter().map(|edge| edge.2).collect::<Vec<f64>>(), vec![3., 4.]);
+    /// ```
+    pub fn emst(&self) -> Vec<(P, P, f64)> {
+        let flat: Flat<P> = Flat::new(self);
+        let mut edges = flat.spanning_tree();
+        edges.sort_by(|a, b| a.2.total_cmp(&b.2));
+        edges
+            .into_iter()
+            .map(|(i, j, dis)| (flat.points[i], flat.points[j], dis))
+            .collect()
+    }
+
+    /// Find the points within `radius` of every point in the tree
+    ///
+    /// This is [`neighbor_pairs`](#method.neighbor_pairs) as an adjacency list. Every point of the
+    /// tree is returned in the order of [`iter`](#method.iter) along with the other points within
+    /// `radius` of it, sorted by distance.
+    pub fn radius_graph(&self, radius: f64) -> Vec<(P, Vec<Neighbor<P>>)> {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let pairs = flat.pairs_within(radius);
+        KdNode::adjacency(flat, pairs)
+    }
+
+    /// Turn pairs of indices into the neighbors of every point
+    fn adjacency(flat: Flat<P>, pairs: Vec<(usize, usize, f64)>) -> Vec<(P, Vec<Neighbor<P>>)> {
+        let mut graph: Vec<(P, Vec<Neighbor<P>>)> = flat
+            .points
+            .iter()
+            .map(|point| (*point, Vec::new()))
+            .collect();
+        for (i, j, _) in pairs {
+            let squared: f64 = squared_distance(&flat.points[i], &flat.points[j]);
+            graph[i].1.push(Neighbor::new(flat.points[j], squared));
+            graph[j].1.push(Neighbor::new(flat.points[i], squared));
+        }
+        for (_, neighbors) in graph.iter_mut() {
+            neighbors.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        }
+        graph
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Send + Sync,
+    P: KdPoint<Scalar = T> + Send + Sync,
+{
+    /// The same as [`neighbor_pairs`](#method.neighbor_pairs) but spread over the rayon thread pool
+    pub fn par_neighbor_pairs(&self, radius: f64) -> Vec<(P, P, f64)> {
+        use rayon::prelude::*;
+
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let pairs = flat.par_pairs_within(radius);
+        pairs
+            .into_par_iter()
+            .map(|(i, j, dis)| (flat.points[i], flat.points[j], dis))
+            .collect()
+    }
+
+    /// The same as [`radius_graph`](#method.radius_graph) but spread over the rayon thread pool
+    pub fn par_radius_graph(&self, radius: f64) -> Vec<(P, Vec<Neighbor<P>>)> {
+        assert!(radius >= 0.0, "Radius must be positive");
+        let flat: Flat<P> = Flat::new(self);
+        let pairs = flat.par_pairs_within(radius);
+        KdNode::adjacency(flat, pairs)
+    }
+}
@@ -1,7 +1,49 @@
 use crate::dim::Dim;
+use crate::KDT;
 
 use std::cmp::Ordering;
-use std::ops::{Add, Mul, Sub};
+
+/// A point that can be stored in a [`KdNode`](../enum.KdNode.html).
+///
+/// Implement this for your own point types to store them in the tree directly
+/// instead of converting them to and from [`Point`](struct.Point.html).
+///
+/// ```rust
+/// use kd_tree_rs::KdNode;
+/// use kd_tree_rs::point::KdPoint;
+///
+/// #[derive(Debug, Copy, Clone, PartialEq)]
+/// struct Position {
+///     x: f32,
+///     y: f32,
+///     z: f32,
+/// }
+///
+/// impl KdPoint for Position {
+///     type Scalar = f32;
+///     const DIMENSIONS: usize = 3;
+///
+///     fn get(&self, dim: usize) -> f32 {
+///         [self.x, self.y, self.z][dim]
+///     }
+/// }
+///
+/// let origin = Position { x: 0., y: 0., z: 0. };
+/// let node: KdNode<f32, Position> = KdNode::build(vec![origin, Position { x: 5., y: 5., z: 5. }]);
+/// assert_eq!(node.nearest_neighbor(origin, 1.0), vec![origin]);
+/// ```
+pub trait KdPoint: Copy {
+    /// The type of each coordinate
+    type Scalar: KDT;
+
+    /// The number of coordinates of the point
+    const DIMENSIONS: usize;
+
+    /// Get the coordinate along the axis `dim`
+    ///
+    /// `dim` will always be less than `DIMENSIONS`.
+    fn get(&self, dim: usize) -> Self::Scalar;
+}
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Point<T: PartialEq> {
@@ -9,59 +51,91 @@ pub struct Point<T: PartialEq> {
     pub y: T,
 }
 
-impl<T: PartialEq + PartialOrd + Into<f64> + Copy> Point<T> {
-    pub(crate) fn gt(&self, rs: &Point<T>, dim: &Dim) -> bool {
+impl<T: KDT> KdPoint for Point<T> {
+    type Scalar = T;
+    const DIMENSIONS: usize = 2;
+
+    fn get(&self, dim: usize) -> T {
         match dim {
-            Dim::X => self.x > rs.x,
-            Dim::Y => self.y > rs.y,
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("Point only has two dimensions"),
         }
     }
+}
+
+/// A point stored along with some data of its own.
+///
+/// The tree only looks at `point`, so `data` comes back with every point a query returns.
+/// [`build_indexed`](../enum.KdNode.html#method.build_indexed) uses it to remember where each
+/// point was in the list the tree was built from.
+///
+/// ```rust
+/// use kd_tree_rs::point::Item;
+/// use kd_tree_rs::{KdNode, Point};
+///
+/// let node = KdNode::build(vec![
+///     Item::new(Point { x: 1., y: 1. }, "home"),
+///     Item::new(Point { x: 9., y: 9. }, "work"),
+/// ]);
+/// assert_eq!(node.n_nearest_neighbor(Item::new(Point { x: 8., y: 7. }, ""), 1)[0].data, "work");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Item<P, D> {
+    pub point: P,
+    pub data: D,
+}
+
+impl<P, D> Item<P, D> {
+    pub fn new(point: P, data: D) -> Self {
+        Item { point, data }
+    }
+}
 
-    pub(crate) fn in_radius(&self, rs: &Point<T>, dim: &Dim, radius: f64) -> bool {
-        let origin: &T = match dim {
-            Dim::X => &self.x,
-            Dim::Y => &self.y,
-        };
+impl<P: KdPoint, D: Copy> KdPoint for Item<P, D> {
+    type Scalar = P::Scalar;
+    const DIMENSIONS: usize = P::DIMENSIONS;
 
-        let point: &T = match dim {
-            Dim::X => &rs.x,
-            Dim::Y => &rs.y,
-        };
+    fn get(&self, dim: usize) -> Self::Scalar {
+        self.point.get(dim)
+    }
+}
 
-        let origin: f64 = (*origin).into();
-        let point: f64 = (*point).into();
+/// A point stored along with its position in the list the tree was built from.
+pub type Indexed<P> = Item<P, usize>;
 
-        (origin + radius > point && origin < point) || (origin - radius < point && origin > point)
+/// Comparisons along a single axis used while building and searching the tree.
+pub(crate) trait AxisCmp: KdPoint {
+    fn gt(&self, rs: &Self, dim: &Dim) -> bool {
+        self.get_dim_value(dim) > rs.get_dim_value(dim)
     }
 
-    pub(crate) fn cmp(&self, rs: &Point<T>, dim: &Dim) -> Ordering {
-        let ls_value: &T = self.get_dim_value(dim);
-        let rs_value: &T = rs.get_dim_value(dim);
-        ls_value.partial_cmp(rs_value).unwrap()
+    fn cmp(&self, rs: &Self, dim: &Dim) -> Ordering {
+        let ls_value: Self::Scalar = self.get_dim_value(dim);
+        let rs_value: Self::Scalar = rs.get_dim_value(dim);
+        ls_value.partial_cmp(&rs_value).unwrap()
     }
 
-    pub(crate) fn get_dim_value(&self, dim: &Dim) -> &T {
-        match dim {
-            Dim::X => &self.x,
-            Dim::Y => &self.y,
-        }
+    fn get_dim_value(&self, dim: &Dim) -> Self::Scalar {
+        self.get(dim.index())
     }
 }
 
-pub fn distance<T>(ls: &Point<T>, rs: &Point<T>) -> f64
-where
-    T: Mul<Output = T>
-        + Sub<Output = T>
-        + Add<Output = T>
-        + Copy
-        + PartialEq
-        + Into<f64>
-        + Mul<Output = T>,
-{
-    let x: T = ls.x - rs.x;
-    let y: T = ls.y - rs.y;
-    let diff: T = x * x + y * y;
-    diff.into().sqrt()
+impl<P: KdPoint> AxisCmp for P {}
+
+/// Euclidean distance between two points
+pub fn distance<P: KdPoint>(ls: &P, rs: &P) -> f64 {
+    squared_distance(ls, rs).sqrt()
+}
+
+/// Squared euclidean distance between two points
+pub fn squared_distance<P: KdPoint>(ls: &P, rs: &P) -> f64 {
+    (0..P::DIMENSIONS)
+        .map(|dim| {
+            let diff: f64 = ls.get(dim).into() - rs.get(dim).into();
+            diff * diff
+        })
+        .sum()
 }
 
 #[test]
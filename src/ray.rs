@@ -0,0 +1,228 @@
+use crate::neighbor::Neighbor;
+use crate::point::KdPoint;
+use crate::{KdNode, KDT};
+
+/// The points `from + t * direction` for `t` between zero and `end`
+///
+/// With `end` set to one this is the segment between two points, with it infinite a ray.
+struct Line {
+    from: Vec<f64>,
+    direction: Vec<f64>,
+    end: f64,
+}
+
+impl Line {
+    fn new<P: KdPoint>(from: &P, to: &P, end: f64) -> Self {
+        let from: Vec<f64> = (0..P::DIMENSIONS).map(|dim| from.get(dim).into()).collect();
+        let direction: Vec<f64> = (0..P::DIMENSIONS)
+            .map(|dim| to.get(dim).into() - from[dim])
+            .collect();
+        Line {
+            from,
+            direction,
+            end,
+        }
+    }
+
+    /// The squared distance from the point to the line along with `t` at the closest point of the line
+    fn point_distance<P: KdPoint>(&self, point: &P) -> (f64, f64) {
+        let mut along: f64 = 0.;
+        let mut length: f64 = 0.;
+        for dim in 0..P::DIMENSIONS {
+            along += (point.get(dim).into() - self.from[dim]) * self.direction[dim];
+            length += self.direction[dim] * self.direction[dim];
+        }
+        let t: f64 = if length > 0. {
+            (along / length).clamp(0., self.end)
+        } else {
+            0.
+        };
+
+        let squared: f64 = (0..P::DIMENSIONS)
+            .map(|dim| {
+                let diff: f64 = point.get(dim).into() - (self.from[dim] + t * self.direction[dim]);
+                diff * diff
+            })
+            .sum();
+        (squared, t)
+    }
+
+    /// The smallest distance from the line to a point in the box between `lower` and `upper`
+    ///
+    /// The line enters and leaves the slab of the box along every axis at most once. Between
+    /// those points the squared distance is a quadratic in `t`, whose minimum is checked in
+    /// every piece. `breaks` is reused between calls to hold the pieces.
+    fn box_distance(&self, lower: &[f64], upper: &[f64], breaks: &mut Vec<f64>) -> f64 {
+        breaks.clear();
+        breaks.extend([0., self.end]);
+        for (dim, direction) in self.direction.iter().enumerate() {
+            if *direction != 0. {
+                for bound in [lower[dim], upper[dim]] {
+                    let t: f64 = (bound - self.from[dim]) / direction;
+                    if 0. < t && t < self.end {
+                        breaks.push(t);
+                    }
+                }
+            }
+        }
+        breaks.sort_by(f64::total_cmp);
+
+        let mut nearest: f64 = f64::INFINITY;
+        for piece in breaks.windows(2) {
+            let (start, end) = (piece[0], piece[1]);
+            let middle: f64 = if end.is_finite() {
+                (start + end) / 2.
+            } else {
+                start + 1.
+            };
+
+            // Sum (from + t * direction - bound)² over the axes the line is outside the box along.
+            let (mut a, mut b, mut c) = (0., 0., 0.);
+            for (dim, direction) in self.direction.iter().enumerate() {
+                let value: f64 = self.from[dim] + middle * direction;
+                let bound: f64 = if value < lower[dim] {
+                    lower[dim]
+                } else if value > upper[dim] {
+                    upper[dim]
+                } else {
+                    continue;
+                };
+                let offset: f64 = self.from[dim] - bound;
+                a += direction * direction;
+                b += 2. * direction * offset;
+                c += offset * offset;
+            }
+
+            let t: f64 = if a > 0. {
+                (-b / (2. * a)).clamp(start, end)
+            } else {
+                start
+            };
+            nearest = nearest.min(a * t * t + b * t + c);
+        }
+        nearest.max(0.).sqrt()
+    }
+
+    /// The smallest distance along the line to a point in the box, not less than zero
+    fn box_reach(&self, lower: &[f64], upper: &[f64]) -> f64 {
+        let mut along: f64 = 0.;
+        let mut length: f64 = 0.;
+        for (dim, direction) in self.direction.iter().enumerate() {
+            along += match direction {
+                d if *d > 0. => d * (lower[dim] - self.from[dim]),
+                d if *d < 0. => d * (upper[dim] - self.from[dim]),
+                _ => 0.,
+            };
+            length += direction * direction;
+        }
+        (along / length.sqrt()).max(0.)
+    }
+}
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find the points within `distance` of the segment between `from` and `to`
+    ///
+    /// Each point is returned along with its distance from the segment, sorted by that
+    /// distance. Subtrees are skipped when the segment passes too far from the region they
+    /// lie in, or their bounding box when the tree stores them.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![
+    ///     Point { x: 5., y: 1. },
+    ///     Point { x: 5., y: 3. },
+    ///     Point { x: 13., y: 0. },
+    /// ]);
+    /// let near = node.segment_neighbors(Point { x: 0., y: 0. }, Point { x: 10., y: 0. }, 2.);
+    /// assert_eq!(near.len(), 1);
+    /// assert_eq!(near[0].distance, 1.);
+    /// ```
+    pub fn segment_neighbors(&self, from: P, to: P, distance: f64) -> Vec<Neighbor<P>> {
+        self.line_neighbors(&Line::new(&from, &to, 1.), distance)
+    }
+
+    /// Find the points within `distance` of the ray starting at `origin` and passing through `toward`
+    ///
+    /// The same as [`segment_neighbors`](#method.segment_neighbors) except the line carries on
+    /// past `toward` forever.
+    pub fn ray_neighbors(&self, origin: P, toward: P, distance: f64) -> Vec<Neighbor<P>> {
+        self.line_neighbors(&Line::new(&origin, &toward, f64::INFINITY), distance)
+    }
+
+    /// Find the first point hit by a ray of the given `thickness`
+    ///
+    /// The ray starts at `origin` and passes through `toward`, it hits every point within
+    /// `thickness` of it. The point that is hit first is returned along with how far the ray
+    /// travelled to reach it, points behind the origin but within `thickness` of it are hit at
+    /// once. Returns `None` when the ray misses every point.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![
+    ///     Point { x: 8., y: 0.5 },
+    ///     Point { x: 4., y: 3. },
+    ///     Point { x: 6., y: -0.5 },
+    /// ]);
+    /// let hit = node.ray_hit(Point { x: 0., y: 0. }, Point { x: 1., y: 0. }, 1.).unwrap();
+    /// assert_eq!(hit.point, Point { x: 6., y: -0.5 });
+    /// assert_eq!(hit.distance, 6.);
+    /// ```
+    pub fn ray_hit(&self, origin: P, toward: P, thickness: f64) -> Option<Neighbor<P>> {
+        let line: Line = Line::new(&origin, &toward, f64::INFINITY);
+        assert!(
+            line.direction.iter().any(|direction| *direction != 0.),
+            "The ray must point somewhere"
+        );
+        let length: f64 = line
+            .direction
+            .iter()
+            .map(|direction| direction * direction)
+            .sum::<f64>()
+            .sqrt();
+
+        let mut hit: Option<Neighbor<P>> = None;
+        let mut breaks: Vec<f64> = Vec::new();
+        self.walk(
+            &mut hit,
+            // Search the side the ray starts in first.
+            |dim, split| line.from[dim] >= split,
+            |hit, lower, upper| {
+                hit.as_ref()
+                    .is_some_and(|hit| line.box_reach(lower, upper) >= hit.distance)
+                    || line.box_distance(lower, upper, &mut breaks) > thickness
+            },
+            |hit, _, point, _, _| {
+                let (squared, t) = line.point_distance(point);
+                let travelled: f64 = t * length;
+                if squared.sqrt() <= thickness
+                    && hit.as_ref().is_none_or(|hit| travelled < hit.distance)
+                {
+                    *hit = Some(Neighbor::new(*point, travelled * travelled));
+                }
+                true
+            },
+        );
+        hit
+    }
+
+    fn line_neighbors(&self, line: &Line, distance: f64) -> Vec<Neighbor<P>> {
+        let mut neighbors: Vec<Neighbor<P>> = Vec::new();
+        let mut breaks: Vec<f64> = Vec::new();
+        self.walk(
+            &mut neighbors,
+            |_, _| false,
+            |_, lower, upper| line.box_distance(lower, upper, &mut breaks) > distance,
+            |neighbors, _, point, _, _| {
+                let (squared, _) = line.point_distance(point);
+                if squared.sqrt() <= distance {
+                    neighbors.push(Neighbor::new(*point, squared));
+                }
+                true
+            },
+        );
+        neighbors.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        neighbors
+    }
+}
@@ -0,0 +1,388 @@
+//! Regions of arbitrary shape to find the points inside of.
+//!
+//! [`query_region`](../enum.KdNode.html#method.query_region) accepts anything implementing
+//! [`Region`](trait.Region.html). The shapes here are two dimensional and look at the first two
+//! coordinates of a point, such as `x` and `y` or longitude and latitude, ignoring any others.
+
+use crate::point::KdPoint;
+use crate::{KdNode, KDT};
+
+/// A region of space that can be tested against points and axis aligned boxes.
+///
+/// Boxes are given as the lower and upper bound along every axis, either of which may be
+/// infinite. The box tests only guide the search so they may be conservative, `intersects_box`
+/// can return `true` for a box that misses the region and `contains_box` can return `false`
+/// for one that lies inside it, but not the other way around.
+pub trait Region<P: KdPoint> {
+    /// Check if the point lies inside the region, points on its boundary included
+    fn contains_point(&self, point: &P) -> bool;
+
+    /// Check if any part of the box may lie inside the region
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool;
+
+    /// Check if all of the box lies inside the region
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool;
+}
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Find the points inside the region
+    ///
+    /// Points are returned in no particular order. Subtrees lying entirely inside the region are
+    /// collected without testing their points, and those entirely outside of it are skipped.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::region::ConvexPolygon;
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<f64> = KdNode::build(vec![
+    ///     Point { x: 1., y: 1. },
+    ///     Point { x: 3., y: 1. },
+    ///     Point { x: 1., y: 3. },
+    /// ]);
+    /// let zone = ConvexPolygon::new(vec![[0., 0.], [4., 0.], [0., 4.]]);
+    /// assert_eq!(node.query_region(&zone).len(), 3);
+    ///
+    /// let corner = ConvexPolygon::new(vec![[0., 0.], [2., 0.], [0., 2.]]);
+    /// assert_eq!(node.query_region(&corner), vec![Point { x: 1., y: 1. }]);
+    /// ```
+    pub fn query_region<R: Region<P> + ?Sized>(&self, region: &R) -> Vec<P> {
+        let mut inside: Vec<P> = Vec::new();
+        self.walk(
+            &mut inside,
+            |_, _| false,
+            |_, lower, upper| !region.intersects_box(lower, upper),
+            |inside, node, point, lower, upper| {
+                if region.contains_box(lower, upper) {
+                    inside.extend(node.iter());
+                    return false;
+                }
+                if region.contains_point(point) {
+                    inside.push(*point);
+                }
+                true
+            },
+        );
+        inside
+    }
+}
+
+/// The first two coordinates of a point
+fn xy<P: KdPoint>(point: &P) -> [f64; 2] {
+    [point.get(0).into(), point.get(1).into()]
+}
+
+/// The distance between two points in the plane
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    let (dx, dy) = (a[0] - b[0], a[1] - b[1]);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// The largest value of `normal · p` over the points `p` of the box
+fn box_max(normal: [f64; 2], lower: &[f64], upper: &[f64]) -> f64 {
+    (0..2)
+        .map(|dim| match normal[dim] {
+            n if n > 0. => n * upper[dim],
+            n if n < 0. => n * lower[dim],
+            // Skipped so an infinite bound along an axis the normal ignores does not give NaN.
+            _ => 0.,
+        })
+        .sum()
+}
+
+/// The smallest value of `normal · p` over the points `p` of the box
+fn box_min(normal: [f64; 2], lower: &[f64], upper: &[f64]) -> f64 {
+    -box_max([-normal[0], -normal[1]], lower, upper)
+}
+
+/// The smallest and largest distance from `center` to the points of the box
+fn box_distances(center: [f64; 2], lower: &[f64], upper: &[f64]) -> (f64, f64) {
+    let (mut near, mut far) = (0., 0.);
+    for dim in 0..2 {
+        let gap: f64 = (lower[dim] - center[dim])
+            .max(center[dim] - upper[dim])
+            .max(0.);
+        let reach: f64 = (center[dim] - lower[dim])
+            .abs()
+            .max((upper[dim] - center[dim]).abs());
+        near += gap * gap;
+        far += reach * reach;
+    }
+    (near.sqrt(), far.sqrt())
+}
+
+/// Twice the signed area of the polygon, positive when its vertices run counter clockwise
+fn signed_area(vertices: &[[f64; 2]]) -> f64 {
+    edges(vertices)
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum()
+}
+
+/// The edges of the polygon, the last one closing it back to the first vertex
+fn edges(vertices: &[[f64; 2]]) -> impl Iterator<Item = ([f64; 2], [f64; 2])> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+/// The lower and upper corner of the box around the vertices
+fn vertex_bounds(vertices: &[[f64; 2]]) -> ([f64; 2], [f64; 2]) {
+    let mut lower: [f64; 2] = [f64::INFINITY; 2];
+    let mut upper: [f64; 2] = [f64::NEG_INFINITY; 2];
+    for vertex in vertices {
+        for dim in 0..2 {
+            lower[dim] = lower[dim].min(vertex[dim]);
+            upper[dim] = upper[dim].max(vertex[dim]);
+        }
+    }
+    (lower, upper)
+}
+
+/// A convex polygon
+///
+/// The vertices may be given in either direction and the polygon is closed from the last
+/// vertex back to the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvexPolygon {
+    /// The vertices in counter clockwise order
+    vertices: Vec<[f64; 2]>,
+}
+
+impl ConvexPolygon {
+    /// Create a polygon from at least three vertices
+    pub fn new(mut vertices: Vec<[f64; 2]>) -> Self {
+        assert!(
+            vertices.len() >= 3,
+            "A polygon needs at least three vertices"
+        );
+        if signed_area(&vertices) < 0. {
+            vertices.reverse();
+        }
+        ConvexPolygon { vertices }
+    }
+
+    /// The outward normal of every edge along with the largest value of `normal · p` inside it
+    fn half_planes(&self) -> impl Iterator<Item = ([f64; 2], f64)> + '_ {
+        edges(&self.vertices).map(|(a, b)| {
+            let normal: [f64; 2] = [b[1] - a[1], a[0] - b[0]];
+            (normal, normal[0] * a[0] + normal[1] * a[1])
+        })
+    }
+}
+
+impl<P: KdPoint> Region<P> for ConvexPolygon {
+    fn contains_point(&self, point: &P) -> bool {
+        let [x, y] = xy(point);
+        self.half_planes()
+            .all(|(normal, offset)| normal[0] * x + normal[1] * y <= offset)
+    }
+
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        // The box and the polygon are apart exactly when one of their edges separates them.
+        let (polygon_lower, polygon_upper) = vertex_bounds(&self.vertices);
+        (0..2).all(|dim| polygon_lower[dim] <= upper[dim] && lower[dim] <= polygon_upper[dim])
+            && self
+                .half_planes()
+                .all(|(normal, offset)| box_min(normal, lower, upper) <= offset)
+    }
+
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        self.half_planes()
+            .all(|(normal, offset)| box_max(normal, lower, upper) <= offset)
+    }
+}
+
+/// A simple polygon, which may be concave but whose edges do not cross each other
+///
+/// The vertices may be given in either direction and the polygon is closed from the last
+/// vertex back to the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<[f64; 2]>,
+    lower: [f64; 2],
+    upper: [f64; 2],
+}
+
+impl Polygon {
+    /// Create a polygon from at least three vertices
+    pub fn new(vertices: Vec<[f64; 2]>) -> Self {
+        assert!(
+            vertices.len() >= 3,
+            "A polygon needs at least three vertices"
+        );
+        let (lower, upper) = vertex_bounds(&vertices);
+        Polygon {
+            vertices,
+            lower,
+            upper,
+        }
+    }
+
+    fn contains(&self, [x, y]: [f64; 2]) -> bool {
+        let mut inside: bool = false;
+        for (a, b) in edges(&self.vertices) {
+            // Points on an edge are inside.
+            let cross: f64 = (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]);
+            let within: bool = a[0].min(b[0]) <= x
+                && x <= a[0].max(b[0])
+                && a[1].min(b[1]) <= y
+                && y <= a[1].max(b[1]);
+            if cross == 0. && within {
+                return true;
+            }
+
+            // Count the edges crossed by a ray from the point towards positive `x`.
+            if (a[1] > y) != (b[1] > y) && x < a[0] + (b[0] - a[0]) * (y - a[1]) / (b[1] - a[1]) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// Check if any edge of the polygon touches the box, which must be finite
+    fn crosses(&self, lower: [f64; 2], upper: [f64; 2]) -> bool {
+        edges(&self.vertices).any(|(a, b)| {
+            // Clip the edge to the box one axis at a time.
+            let (mut start, mut end) = (0f64, 1f64);
+            for dim in 0..2 {
+                let delta: f64 = b[dim] - a[dim];
+                if delta == 0. {
+                    if a[dim] < lower[dim] || a[dim] > upper[dim] {
+                        return false;
+                    }
+                    continue;
+                }
+                let (enter, exit) = ((lower[dim] - a[dim]) / delta, (upper[dim] - a[dim]) / delta);
+                start = start.max(enter.min(exit));
+                end = end.min(enter.max(exit));
+            }
+            start <= end
+        })
+    }
+}
+
+impl<P: KdPoint> Region<P> for Polygon {
+    fn contains_point(&self, point: &P) -> bool {
+        self.contains(xy(point))
+    }
+
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        // Only the part of the box around the polygon matters, which also makes it finite.
+        let lower: [f64; 2] = [lower[0].max(self.lower[0]), lower[1].max(self.lower[1])];
+        let upper: [f64; 2] = [upper[0].min(self.upper[0]), upper[1].min(self.upper[1])];
+        if lower[0] > upper[0] || lower[1] > upper[1] {
+            return false;
+        }
+
+        // Without an edge touching it the box is either entirely inside or entirely outside.
+        self.crosses(lower, upper) || self.contains(lower)
+    }
+
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        let within: bool =
+            (0..2).all(|dim| self.lower[dim] <= lower[dim] && upper[dim] <= self.upper[dim]);
+        within
+            && !self.crosses([lower[0], lower[1]], [upper[0], upper[1]])
+            && self.contains([lower[0], lower[1]])
+    }
+}
+
+/// A circle around `center`, the points within `radius` of it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Circle {
+    pub center: [f64; 2],
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: [f64; 2], radius: f64) -> Self {
+        Circle { center, radius }
+    }
+}
+
+impl<P: KdPoint> Region<P> for Circle {
+    fn contains_point(&self, point: &P) -> bool {
+        distance(xy(point), self.center) <= self.radius
+    }
+
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        box_distances(self.center, lower, upper).0 <= self.radius
+    }
+
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        box_distances(self.center, lower, upper).1 <= self.radius
+    }
+}
+
+/// A ring around `center`, the points between `inner` and `outer` of it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Annulus {
+    pub center: [f64; 2],
+    pub inner: f64,
+    pub outer: f64,
+}
+
+impl Annulus {
+    pub fn new(center: [f64; 2], inner: f64, outer: f64) -> Self {
+        assert!(
+            inner <= outer,
+            "The inner radius must not be larger than the outer radius"
+        );
+        Annulus {
+            center,
+            inner,
+            outer,
+        }
+    }
+}
+
+impl<P: KdPoint> Region<P> for Annulus {
+    fn contains_point(&self, point: &P) -> bool {
+        let distance: f64 = distance(xy(point), self.center);
+        self.inner <= distance && distance <= self.outer
+    }
+
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        let (near, far) = box_distances(self.center, lower, upper);
+        near <= self.outer && far >= self.inner
+    }
+
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        let (near, far) = box_distances(self.center, lower, upper);
+        near >= self.inner && far <= self.outer
+    }
+}
+
+/// The points on one side of a line, those where `normal · p <= offset`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HalfPlane {
+    pub normal: [f64; 2],
+    pub offset: f64,
+}
+
+impl HalfPlane {
+    pub fn new(normal: [f64; 2], offset: f64) -> Self {
+        HalfPlane { normal, offset }
+    }
+
+    /// The points on the left of the line running from `from` towards `to`
+    pub fn left_of(from: [f64; 2], to: [f64; 2]) -> Self {
+        let normal: [f64; 2] = [to[1] - from[1], from[0] - to[0]];
+        HalfPlane::new(normal, normal[0] * from[0] + normal[1] * from[1])
+    }
+}
+
+impl<P: KdPoint> Region<P> for HalfPlane {
+    fn contains_point(&self, point: &P) -> bool {
+        let [x, y] = xy(point);
+        self.normal[0] * x + self.normal[1] * y <= self.offset
+    }
+
+    fn intersects_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        box_min(self.normal, lower, upper) <= self.offset
+    }
+
+    fn contains_box(&self, lower: &[f64], upper: &[f64]) -> bool {
+        box_max(self.normal, lower, upper) <= self.offset
+    }
+}
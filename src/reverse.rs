@@ -0,0 +1,112 @@
+use crate::neighbor::Neighbor;
+use crate::point::{squared_distance, KdPoint};
+use crate::search::SearchScratch;
+use crate::{KdNode, KDT};
+
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+impl<T, P> KdNode<T, P>
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+    P: KdPoint<Scalar = T>,
+{
+    /// Find the stored points that would have the origin among their `k` nearest neighbors
+    ///
+    /// A point is returned when fewer than `k` other stored points are strictly closer to it
+    /// than the origin is. Results are sorted by their distance from the origin.
+    ///
+    /// Every point looked at is kept as a candidate, and a subtree is skipped once `k` of them
+    /// are closer than the origin to all of the region it lies in. The remaining candidates are
+    /// confirmed with a nearest neighbor search of their own.
+    ///
+    /// ```rust
+    /// use kd_tree_rs::{KdNode, Point};
+    ///
+    /// let node: KdNode<i32> = KdNode::build(vec![Point { x: 0, y: 0 }, Point { x: 1, y: 0 }, Point { x: 10, y: 0 }]);
+    /// let reverse = node.reverse_nearest_neighbors(Point { x: 8, y: 0 }, 1);
+    /// assert_eq!(reverse.len(), 1);
+    /// assert_eq!(reverse[0].point, Point { x: 10, y: 0 });
+    /// ```
+    pub fn reverse_nearest_neighbors(&self, origin: P, k: usize) -> Vec<Neighbor<P>> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut found: (Vec<P>, Vec<P>) = (Vec::new(), Vec::new());
+        self.walk(
+            &mut found,
+            // Search the side of the split holding the origin first.
+            |dim, split| origin.get(dim).into() >= split,
+            |(candidates, _), lower, upper| covered(&origin, candidates, lower, upper, k),
+            |(candidates, unconfirmed), _, point, _, _| {
+                let to_origin: f64 = squared_distance(point, &origin);
+                let closer: usize = candidates
+                    .iter()
+                    .filter(|other| squared_distance(point, *other) < to_origin)
+                    .count();
+                if closer < k {
+                    unconfirmed.push(*point);
+                }
+                candidates.push(*point);
+                true
+            },
+        );
+
+        let mut reverse: Vec<Neighbor<P>> = Vec::new();
+        let mut nearest: Vec<Neighbor<P>> = Vec::with_capacity(k + 1);
+        let mut scratch: SearchScratch<T, P> = SearchScratch::new();
+        for point in found.1 {
+            let to_origin: f64 = squared_distance(&point, &origin);
+            // The point finds itself at a distance of zero, every other point closer than the origin counts.
+            self.n_nearest_neighbor_into(point, k + 1, &mut nearest, &mut scratch);
+            let closer: usize = nearest
+                .iter()
+                .filter(|neighbor| neighbor.squared_distance < to_origin)
+                .count();
+            if to_origin == 0. || closer <= k {
+                reverse.push(Neighbor::new(point, to_origin));
+            }
+        }
+        reverse.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        reverse
+    }
+}
+
+/// Check if every point in the region between `lower` and `upper` has at least `k` other
+/// candidates closer to it than the origin.
+fn covered<P: KdPoint>(
+    origin: &P,
+    candidates: &[P],
+    lower: &[f64],
+    upper: &[f64],
+    k: usize,
+) -> bool {
+    let mut covering: usize = 0;
+    let mut inside: bool = false;
+    for candidate in candidates {
+        // Points closer to the candidate than the origin lie on one side of the plane halfway
+        // between them. The region is on that side when the point of the region farthest
+        // along the plane's normal is.
+        let mut farthest: f64 = 0.;
+        let mut contained: bool = true;
+        for dim in 0..P::DIMENSIONS {
+            let origin_value: f64 = origin.get(dim).into();
+            let candidate_value: f64 = candidate.get(dim).into();
+            let normal: f64 = origin_value - candidate_value;
+            let corner: f64 = if normal > 0. { upper[dim] } else { lower[dim] };
+            if normal != 0. {
+                farthest += normal * (2. * corner - origin_value - candidate_value);
+            }
+            contained &= lower[dim] <= candidate_value && candidate_value <= upper[dim];
+        }
+
+        if farthest < 0. {
+            covering += 1;
+            inside |= contained;
+        }
+    }
+
+    // A candidate inside the region may be the point it is being compared with.
+    covering - inside as usize >= k
+}
@@ -0,0 +1,105 @@
+//! Thinning out dense sets of points.
+
+use crate::point::KdPoint;
+use crate::{KdNode, KDT};
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// The points of a single cell of [`voxel_grid`](fn.voxel_grid.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voxel<P> {
+    /// The first point found in the cell
+    pub first: P,
+    /// The mean of the points in the cell
+    pub centroid: Vec<f64>,
+    /// The number of points in the cell
+    pub count: usize,
+}
+
+/// Group the points into cubes of the given `size` along every axis
+///
+/// The cells are aligned with zero, every cell holding at least one point is returned in the
+/// order of its first point. Keep the `first` point of each cell to thin out the points while
+/// keeping points from the original set, or the `centroid` to smooth them.
+///
+/// The points can come from a slice or from [`iter`](../enum.KdNode.html#method.iter) of a tree.
+///
+/// ```rust
+/// use kd_tree_rs::sample::voxel_grid;
+/// use kd_tree_rs::Point;
+///
+/// let points = vec![Point { x: 0.5, y: 0.5 }, Point { x: 5., y: 5. }, Point { x: 1.5, y: 0.5 }];
+/// let voxels = voxel_grid(&points, 2.);
+/// assert_eq!(voxels.len(), 2);
+/// assert_eq!((voxels[0].first, voxels[0].count), (points[0], 2));
+/// assert_eq!(voxels[0].centroid, vec![1., 0.5]);
+/// ```
+pub fn voxel_grid<'a, P: KdPoint + 'a>(
+    points: impl IntoIterator<Item = &'a P>,
+    size: f64,
+) -> Vec<Voxel<P>> {
+    assert!(size > 0., "The size of the cells must be positive");
+    let mut cells: HashMap<Vec<i64>, usize> = HashMap::new();
+    let mut voxels: Vec<Voxel<P>> = Vec::new();
+    for point in points {
+        let cell: Vec<i64> = (0..P::DIMENSIONS)
+            .map(|dim| (point.get(dim).into() / size).floor() as i64)
+            .collect();
+        let voxel: &mut Voxel<P> = match cells.get(&cell) {
+            Some(i) => &mut voxels[*i],
+            None => {
+                cells.insert(cell, voxels.len());
+                voxels.push(Voxel {
+                    first: *point,
+                    centroid: vec![0.; P::DIMENSIONS],
+                    count: 0,
+                });
+                voxels.last_mut().unwrap()
+            }
+        };
+        // Sum the points first, they are divided by the count once every point is in.
+        for (dim, sum) in voxel.centroid.iter_mut().enumerate() {
+            *sum += point.get(dim).into();
+        }
+        voxel.count += 1;
+    }
+
+    for voxel in voxels.iter_mut() {
+        for sum in voxel.centroid.iter_mut() {
+            *sum /= voxel.count as f64;
+        }
+    }
+    voxels
+}
+
+/// Pick points that are all more than `separation` apart
+///
+/// The points are taken in order and kept unless one of the points already kept is within
+/// `separation` of it, which is checked with
+/// [`any_within_radius`](../enum.KdNode.html#method.any_within_radius) against a tree the kept
+/// points are inserted into. Every point left out is within `separation` of a kept point.
+///
+/// ```rust
+/// use kd_tree_rs::sample::poisson_disk;
+/// use kd_tree_rs::Point;
+///
+/// let points = vec![Point { x: 0., y: 0. }, Point { x: 1., y: 0. }, Point { x: 2.5, y: 0. }, Point { x: 3., y: 0. }];
+/// assert_eq!(poisson_disk(&points, 2.), vec![points[0], points[2]]);
+/// ```
+pub fn poisson_disk<'a, T, P>(points: impl IntoIterator<Item = &'a P>, separation: f64) -> Vec<P>
+where
+    T: KDT + Mul<Output = T> + Sub<Output = T> + Add<Output = T> + Debug,
+    P: KdPoint<Scalar = T> + 'a,
+{
+    let mut kept: Vec<P> = Vec::new();
+    let mut tree: KdNode<T, P> = KdNode::new();
+    for point in points {
+        if !tree.any_within_radius(*point, separation) {
+            tree.insert_point(*point);
+            kept.push(*point);
+        }
+    }
+    kept
+}
@@ -0,0 +1,39 @@
+use crate::point::KdPoint;
+use crate::{KdNode, KDT};
+
+/// Reusable buffers for searching a tree.
+///
+/// Passing the same scratch to the `_into` queries of a tree lets repeated
+/// queries reuse its allocations instead of allocating new buffers each time.
+///
+/// ```rust
+/// use kd_tree_rs::{KdNode, Neighbor, Point, SearchScratch};
+///
+/// let node: KdNode<f64> = KdNode::build(vec![Point { x: 1., y: 1. }, Point { x: 2., y: 2. }]);
+/// let mut scratch = SearchScratch::new();
+/// let mut nearest: Vec<Neighbor<Point<f64>>> = Vec::new();
+///
+/// for x in 0..10 {
+///     node.nearest_neighbor_into(Point { x: x as f64, y: 1. }, 1., &mut nearest, &mut scratch);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SearchScratch<'a, T: KDT, P: KdPoint<Scalar = T>> {
+    pub(crate) best_queue: Vec<(&'a KdNode<T, P>, f64)>,
+    pub(crate) parent_queue: Vec<&'a KdNode<T, P>>,
+}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> SearchScratch<'a, T, P> {
+    pub fn new() -> Self {
+        SearchScratch {
+            best_queue: Vec::new(),
+            parent_queue: Vec::new(),
+        }
+    }
+}
+
+impl<'a, T: KDT, P: KdPoint<Scalar = T>> Default for SearchScratch<'a, T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,58 @@
 #[cfg(test)]
 #[allow(unused_variables)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::dim::Dim;
     use crate::point::Point;
     use crate::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts the allocations made by each thread so tests can check queries do not allocate.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|count| count.get())
+    }
+
+    /// Deterministic pseudo random points in `[0, 100)`
+    fn random_points(n: usize, mut seed: u64) -> Vec<Point<f64>> {
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 * 100.
+        };
+        (0..n)
+            .map(|_| Point {
+                x: next(),
+                y: next(),
+            })
+            .collect()
+    }
 
     const TEST_POINTS_I32: &[Point<i32>] = &[
         Point { x: 1, y: 8 }, // P1
@@ -31,28 +80,40 @@ mod tests {
     fn test_insert_empty() {
         let mut node: KdNode<i32> = KdNode::new();
         assert_eq!(node, Empty);
+        assert_eq!(node, KdNode::default());
 
-        // Tree Root
+        // Tree Root (sorted on X)
         node.insert(1, 1);
         // Second level of tree (sorted on Y)
         node.insert_point(Point { x: 2, y: 2 });
+        // Third level of tree (sorted on X)
         node.insert_point(Point { x: 2, y: -12 });
         assert_eq!(
             node,
             Node {
                 point: Point { x: 1, y: 1 },
+                size: 3,
+                bounds: Some(Bounds::of(&[
+                    Point { x: 1, y: 1 },
+                    Point { x: 2, y: 2 },
+                    Point { x: 2, y: -12 }
+                ])),
                 dim: Dim::X,
-                right: Box::new(Node {
-                    point: Point { x: 2, y: -12 },
-                    dim: Dim::Y,
-                    left: Box::new(Empty),
-                    right: Box::new(Empty)
-                }),
+                right: Box::new(Empty),
                 left: Box::new(Node {
                     point: Point { x: 2, y: 2 },
+                    size: 2,
+                    bounds: Some(Bounds::of(&[Point { x: 2, y: 2 }, Point { x: 2, y: -12 }])),
                     dim: Dim::Y,
                     left: Box::new(Empty),
-                    right: Box::new(Empty)
+                    right: Box::new(Node {
+                        point: Point { x: 2, y: -12 },
+                        size: 1,
+                        bounds: Some(Bounds::of(&[Point { x: 2, y: -12 }])),
+                        dim: Dim::X,
+                        left: Box::new(Empty),
+                        right: Box::new(Empty)
+                    })
                 })
             }
         );
@@ -70,18 +131,26 @@ mod tests {
             node,
             Node {
                 point: Point { x: 4, y: 9 },
+                size: 8,
+                bounds: None,
                 dim: Dim::X,
                 right: Box::new(Node {
                     point: Point { x: 3, y: 6 },
+                    size: 3,
+                    bounds: None,
                     dim: Dim::Y,
                     left: Box::new(Node {
                         point: Point { x: 1, y: 8 },
+                        size: 1,
+                        bounds: None,
                         dim: Dim::X,
                         left: Box::new(Empty),
                         right: Box::new(Empty)
                     }),
                     right: Box::new(Node {
                         point: Point { x: 2, y: 2 },
+                        size: 1,
+                        bounds: None,
                         dim: Dim::X,
                         left: Box::new(Empty),
                         right: Box::new(Empty)
@@ -89,19 +158,27 @@ mod tests {
                 }),
                 left: Box::new(Node {
                     point: Point { x: 7, y: 3 },
+                    size: 4,
+                    bounds: None,
                     dim: Dim::Y,
                     right: Box::new(Node {
                         point: Point { x: 9, y: 1 },
+                        size: 1,
+                        bounds: None,
                         dim: Dim::X,
                         left: Box::new(KdNode::Empty),
                         right: Box::new(KdNode::Empty)
                     }),
                     left: Box::new(Node {
                         point: Point { x: 8, y: 8 },
+                        size: 2,
+                        bounds: None,
                         dim: Dim::X,
                         right: Box::new(KdNode::Empty),
                         left: Box::new(Node {
                             point: Point { x: 9, y: 9 },
+                            size: 1,
+                            bounds: None,
                             dim: Dim::Y,
                             left: Box::new(KdNode::Empty),
                             right: Box::new(KdNode::Empty)
@@ -169,27 +246,283 @@ mod tests {
             assert!(TEST_POINTS_F64.to_vec().contains(&point))
         }
         assert_eq!(nearest.len(), 4);
+    }
+
+    #[test]
+    fn test_nearest_neighbor_with_distance() {
+        let node: KdNode<f64> = KdNode::build(TEST_POINTS_F64.to_vec());
+        let origin: Point<f64> = Point { x: 8.1, y: 8.1 };
+
+        let nearest: Vec<Neighbor<Point<f64>>> = node.nearest_neighbor_with_distance(origin, 1.5);
+        assert_eq!(nearest.len(), 2);
+        assert_eq!(nearest[0].point, Point { x: 8., y: 8. });
+        assert_eq!(nearest[1].point, Point { x: 9., y: 9. });
+        assert!((nearest[0].distance - 0.02f64.sqrt()).abs() < 1e-9);
+        assert!((nearest[1].squared_distance - 1.62).abs() < 1e-9);
+        for neighbor in &nearest {
+            assert_eq!(
+                neighbor.squared_distance,
+                point::squared_distance(&origin, &neighbor.point)
+            );
+        }
+
+        let all: Vec<Neighbor<Point<f64>>> = node.nearest_neighbor_with_distance(origin, 100.);
+        assert_eq!(all.len(), TEST_POINTS_F64.len());
+        assert!(all
+            .windows(2)
+            .all(|pair| pair[0].distance <= pair[1].distance));
+    }
+
+    #[test]
+    fn test_n_nearest_neighbor_with_distance() {
+        let node: KdNode<f64> = KdNode::build(TEST_POINTS_F64.to_vec());
+        let origin: Point<f64> = Point { x: 0., y: 0. };
+
+        let mut expected: Vec<(Point<f64>, f64)> = TEST_POINTS_F64
+            .iter()
+            .map(|point| (*point, point::distance(&origin, point)))
+            .collect();
+        expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        for max in 0..=TEST_POINTS_F64.len() + 1 {
+            let nearest: Vec<(Point<f64>, f64)> = node
+                .n_nearest_neighbor_with_distance(origin, max)
+                .into_iter()
+                .map(<(Point<f64>, f64)>::from)
+                .collect();
+            assert_eq!(
+                nearest,
+                expected.iter().take(max).copied().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbor_empty_tree() {
+        let node: KdNode<f64> = KdNode::new();
+        assert_eq!(node.nearest_neighbor(Point { x: 0., y: 0. }, 1.), vec![]);
+        assert_eq!(
+            node.n_nearest_neighbor_with_distance(Point { x: 0., y: 0. }, 3),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_queries_into_do_not_allocate() {
+        let node: KdNode<f64> = KdNode::build(random_points(2_000, 7));
+        let origins: Vec<Point<f64>> = random_points(200, 11);
+        let mut scratch: SearchScratch<f64, Point<f64>> = SearchScratch::new();
+        let mut out: Vec<Neighbor<Point<f64>>> = Vec::new();
+
+        let mut run = |out: &mut Vec<Neighbor<Point<f64>>>| {
+            for origin in &origins {
+                node.nearest_neighbor_into(*origin, 10., out, &mut scratch);
+                node.n_nearest_neighbor_into(*origin, 8, out, &mut scratch);
+                node.nearest_neighbor_metric_into(
+                    *origin,
+                    500_000.,
+                    &Haversine::EARTH,
+                    out,
+                    &mut scratch,
+                );
+            }
+        };
+
+        // Grow the buffers to their steady state size.
+        run(&mut out);
+
+        let before: usize = allocations();
+        run(&mut out);
+        assert_eq!(allocations() - before, 0);
+
+        // The results match the allocating queries.
+        let origin: Point<f64> = origins[0];
+        node.nearest_neighbor_into(origin, 10., &mut out, &mut SearchScratch::new());
+        assert_eq!(out, node.nearest_neighbor_with_distance(origin, 10.));
+        node.n_nearest_neighbor_into(origin, 8, &mut out, &mut SearchScratch::new());
+        assert_eq!(out, node.n_nearest_neighbor_with_distance(origin, 8));
+    }
 
+    /// The number of nodes on the longest path from the root to a leaf
+    fn height<T: KDT + std::fmt::Debug, P: KdPoint<Scalar = T>>(node: &KdNode<T, P>) -> usize {
+        let mut tallest: usize = 0;
+        let mut stack: Vec<(&KdNode<T, P>, usize)> = vec![(node, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            if let Node { left, right, .. } = node {
+                tallest = tallest.max(depth + 1);
+                stack.push((left, depth + 1));
+                stack.push((right, depth + 1));
+            }
+        }
+        tallest
+    }
+
+    #[test]
+    fn test_insert_monotonic() {
+        const N: i32 = 1_000_000;
+        let mut node: KdNode<i32> = KdNode::new();
+        for i in 0..N {
+            node.insert(i, i);
+        }
+
+        assert_eq!(node.len(), N as usize);
+        assert!(height(&node) < 100, "{}", height(&node));
+        assert_eq!(
+            node.nearest_neighbor_x_y(N / 2, N / 2, 1.),
+            vec![Point { x: N / 2, y: N / 2 }]
+        );
+        assert_eq!(
+            node.n_nearest_neighbor(Point { x: N, y: N }, 2),
+            vec![Point { x: N - 1, y: N - 1 }, Point { x: N - 2, y: N - 2 }]
+        );
+        assert_eq!(node.nearest_neighbor_x_y(-5, -5, 1.), vec![]);
+    }
+
+    #[test]
+    fn test_degenerate_tree() {
+        // Build a tree that is a single long path, as inserting sorted points without rebalancing would.
+        const N: i32 = 1_000_000;
+        let mut node: KdNode<i32> = Empty;
+        for i in (0..N).rev() {
+            node = Node {
+                point: Point { x: i, y: i },
+                dim: Dim::from_depth(i as usize, 2),
+                left: Box::new(node),
+                right: Box::new(Empty),
+                size: (N - i) as usize,
+                bounds: None,
+            };
+        }
+
+        assert_eq!(node.len(), N as usize);
+        assert_eq!(height(&node), N as usize);
+        assert_eq!(
+            node.nearest_neighbor_x_y(N - 1, N - 1, 1.),
+            vec![Point { x: N - 1, y: N - 1 }]
+        );
+        assert_eq!(
+            node.nearest_neighbor_x_y(N / 2, N / 2, 1.),
+            vec![Point { x: N / 2, y: N / 2 }]
+        );
+        assert_eq!(
+            node.n_nearest_neighbor(Point { x: 0, y: 0 }, 1),
+            vec![Point { x: 0, y: 0 }]
+        );
+
+        node.insert(N, N);
+        assert_eq!(node.len(), N as usize + 1);
+        drop(node);
+    }
+
+    #[test]
+    fn test_insert_into_built_tree() {
+        let mut node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        node.insert(5, 5);
+        node.insert(10, 0);
+
+        assert_eq!(
+            node.nearest_neighbor_x_y(5, 5, 0.5),
+            vec![Point { x: 5, y: 5 }]
+        );
+        assert_eq!(
+            node.nearest_neighbor_x_y(10, 0, 0.5),
+            vec![Point { x: 10, y: 0 }]
+        );
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    struct Point3 {
+        x: f64,
+        y: f64,
+        z: f64,
+    }
+
+    impl KdPoint for Point3 {
+        type Scalar = f64;
+        const DIMENSIONS: usize = 3;
+
+        fn get(&self, dim: usize) -> f64 {
+            [self.x, self.y, self.z][dim]
+        }
+    }
 
+    #[test]
+    fn test_custom_point() {
+        let points: Vec<Point3> = TEST_POINTS_F64
+            .iter()
+            .map(|p| Point3 {
+                x: p.x,
+                y: p.y,
+                z: p.x + p.y,
+            })
+            .collect();
+        let mut node: KdNode<f64, Point3> = KdNode::build(points.clone());
+
+        match &node {
+            Node { dim, left, .. } => {
+                assert_eq!(*dim, Dim::X);
+                match left.as_ref() {
+                    Node { dim, left, .. } => {
+                        assert_eq!(*dim, Dim::Y);
+                        assert!(matches!(left.as_ref(), Node { dim: Dim::Z, .. }));
+                    }
+                    Empty => panic!(),
+                }
+            }
+            Empty => panic!(),
+        }
+
+        let origin = Point3 {
+            x: 8.1,
+            y: 8.1,
+            z: 16.2,
+        };
+        assert_eq!(
+            node.nearest_neighbor(origin, 2.),
+            vec![Point3 {
+                x: 8.,
+                y: 8.,
+                z: 16.
+            }]
+        );
+
+        let inserted = Point3 {
+            x: 8.,
+            y: 8.,
+            z: 17.,
+        };
+        node.insert_point(inserted);
+        assert_eq!(
+            node.nearest_neighbor(origin, 2.),
+            vec![
+                Point3 {
+                    x: 8.,
+                    y: 8.,
+                    z: 16.
+                },
+                inserted
+            ]
+        );
     }
 
     #[test]
     fn test_insert_sorted() {
-        let mut points: Vec<(&KdNode<f64>, f64)> = vec![(&Empty, 1.), (&Empty, 2.), (&Empty, 3.)];
-        KdNode::insert_sorted(&mut points, (&Empty, 0.));
-        KdNode::insert_sorted(&mut points, (&Empty, 2.5));
-        KdNode::insert_sorted(&mut points, (&Empty, 3.5));
-        KdNode::insert_sorted(&mut points, (&Empty, 5.));
+        let empty: KdNode<f64> = Empty;
+        let mut points: Vec<(&KdNode<f64>, f64)> = vec![(&empty, 1.), (&empty, 2.), (&empty, 3.)];
+        KdNode::insert_sorted(&mut points, (&empty, 0.));
+        KdNode::insert_sorted(&mut points, (&empty, 2.5));
+        KdNode::insert_sorted(&mut points, (&empty, 3.5));
+        KdNode::insert_sorted(&mut points, (&empty, 5.));
         assert_eq!(
             points,
             vec![
-                (&Empty, 0.),
-                (&Empty, 1.),
-                (&Empty, 2.),
-                (&Empty, 2.5),
-                (&Empty, 3.),
-                (&Empty, 3.5),
-                (&Empty, 5.),
+                (&empty, 0.),
+                (&empty, 1.),
+                (&empty, 2.),
+                (&empty, 2.5),
+                (&empty, 3.),
+                (&empty, 3.5),
+                (&empty, 5.),
             ]
         );
     }
@@ -198,7 +531,9 @@ mod tests {
     fn test_drill_down() {
         let node: KdNode<f64> = KdNode::build(TEST_POINTS_F64.to_vec());
 
-        match node.drill_down(Point { x: 0., y: 0. }).pop().unwrap() {
+        let mut parents: Vec<&KdNode<f64>> = Vec::new();
+        node.drill_down(Point { x: 0., y: 0. }, &mut parents);
+        match parents.pop().unwrap() {
             Empty => {
                 panic!()
             }
@@ -207,4 +542,1605 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_iter() {
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        let points: Vec<Point<i32>> = node.iter().copied().collect();
+        assert_eq!(node.iter().len(), 8);
+        assert_eq!(points[0], Point { x: 4, y: 9 });
+        assert_eq!(points[1], Point { x: 7, y: 3 });
+
+        let mut sorted: Vec<Point<i32>> = points.clone();
+        sorted.sort_by_key(|point| (point.x, point.y));
+        assert_eq!(sorted, TEST_POINTS_I32.to_vec());
+        assert_eq!((&node).into_iter().count(), 8);
+        assert_eq!(KdNode::<i32>::new().iter().next(), None);
+    }
+
+    /// Order pairs of points so they can be compared with a brute force search
+    fn sorted_pairs(
+        pairs: Vec<(Point<f64>, Point<f64>, f64)>,
+    ) -> Vec<(Point<f64>, Point<f64>, f64)> {
+        let key = |point: &Point<f64>| (point.x, point.y);
+        let mut pairs: Vec<(Point<f64>, Point<f64>, f64)> = pairs
+            .into_iter()
+            .map(|(a, b, dis)| {
+                if key(&a) <= key(&b) {
+                    (a, b, dis)
+                } else {
+                    (b, a, dis)
+                }
+            })
+            .collect();
+        pairs.sort_by(|a, b| {
+            (key(&a.0), key(&a.1))
+                .partial_cmp(&(key(&b.0), key(&b.1)))
+                .unwrap()
+        });
+        pairs
+    }
+
+    fn brute_force_pairs(points: &[Point<f64>], radius: f64) -> Vec<(Point<f64>, Point<f64>, f64)> {
+        let mut pairs: Vec<(Point<f64>, Point<f64>, f64)> = Vec::new();
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                let dis: f64 = point::distance(a, b);
+                if dis <= radius {
+                    pairs.push((*a, *b, dis));
+                }
+            }
+        }
+        sorted_pairs(pairs)
+    }
+
+    #[test]
+    fn test_neighbor_pairs() {
+        let points: Vec<Point<f64>> = random_points(1_000, 5);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        for radius in [0., 1., 4., 30.] {
+            let expected = brute_force_pairs(&points, radius);
+            assert_eq!(sorted_pairs(node.neighbor_pairs(radius)), expected);
+            #[cfg(feature = "rayon")]
+            assert_eq!(sorted_pairs(node.par_neighbor_pairs(radius)), expected);
+        }
+
+        assert_eq!(KdNode::<f64>::new().neighbor_pairs(1.), vec![]);
+        let node: KdNode<i32> = KdNode::build(vec![
+            Point { x: 1, y: 1 },
+            Point { x: 1, y: 1 },
+            Point { x: 3, y: 1 },
+        ]);
+        assert_eq!(node.neighbor_pairs(0.).len(), 1);
+        assert_eq!(node.neighbor_pairs(2.).len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Radius must be positive")]
+    fn test_neighbor_pairs_negative_radius() {
+        KdNode::build(TEST_POINTS_F64.to_vec()).neighbor_pairs(-1.);
+    }
+
+    #[test]
+    fn test_radius_graph() {
+        let points: Vec<Point<f64>> = random_points(500, 6);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        let graph = node.radius_graph(6.);
+        assert_eq!(
+            graph.iter().map(|(point, _)| *point).collect::<Vec<_>>(),
+            node.iter().copied().collect::<Vec<_>>()
+        );
+
+        for (point, neighbors) in &graph {
+            let mut expected: Vec<f64> = points
+                .iter()
+                .filter(|other| *other != point)
+                .map(|other| point::distance(point, other))
+                .filter(|dis| *dis <= 6.)
+                .collect();
+            expected.sort_by(f64::total_cmp);
+            assert_eq!(
+                neighbors
+                    .iter()
+                    .map(|neighbor| neighbor.distance)
+                    .collect::<Vec<_>>(),
+                expected
+            );
+        }
+
+        #[cfg(feature = "rayon")]
+        assert_eq!(node.par_radius_graph(6.), graph);
+    }
+
+    /// The distances to the `k` nearest other points of every point, found by brute force
+    fn brute_force_knn(points: &[Point<f64>], point: &Point<f64>, k: usize) -> Vec<f64> {
+        let mut distances: Vec<f64> = points
+            .iter()
+            .filter(|other| *other != point)
+            .map(|other| point::distance(point, other))
+            .collect();
+        distances.sort_by(f64::total_cmp);
+        distances.truncate(k);
+        distances
+    }
+
+    #[test]
+    fn test_knn_graph() {
+        let points: Vec<Point<f64>> = random_points(600, 7);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        for k in [0, 1, 5, 40] {
+            let graph = node.knn_graph(k);
+            assert_eq!(
+                graph.iter().map(|(point, _)| *point).collect::<Vec<_>>(),
+                node.iter().copied().collect::<Vec<_>>()
+            );
+            for (point, neighbors) in &graph {
+                let distances: Vec<f64> =
+                    neighbors.iter().map(|neighbor| neighbor.distance).collect();
+                assert_eq!(distances, brute_force_knn(&points, point, k));
+            }
+            #[cfg(feature = "rayon")]
+            assert_eq!(node.par_knn_graph(k), graph);
+        }
+
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        assert!(node
+            .knn_graph(20)
+            .iter()
+            .all(|(_, neighbors)| neighbors.len() == 7));
+    }
+
+    #[test]
+    fn test_knn_graph_symmetric() {
+        let node: KdNode<f64> = KdNode::build(random_points(400, 8));
+        let key = |point: &Point<f64>| (point.x.to_bits(), point.y.to_bits());
+        type Graph = Vec<(Point<f64>, Vec<Neighbor<Point<f64>>>)>;
+        let edges = |graph: &Graph| -> std::collections::HashSet<_> {
+            graph
+                .iter()
+                .flat_map(|(point, neighbors)| {
+                    neighbors
+                        .iter()
+                        .map(move |neighbor| (key(point), key(&neighbor.point)))
+                })
+                .collect()
+        };
+
+        let directed = edges(&node.knn_graph(4));
+        let mutual = edges(&node.knn_graph_symmetric(4, Symmetry::Mutual));
+        let union = edges(&node.knn_graph_symmetric(4, Symmetry::Union));
+
+        assert!(mutual.iter().all(|(a, b)| mutual.contains(&(*b, *a))
+            && directed.contains(&(*a, *b))
+            && directed.contains(&(*b, *a))));
+        assert!(union.iter().all(|(a, b)| union.contains(&(*b, *a))
+            && (directed.contains(&(*a, *b)) || directed.contains(&(*b, *a)))));
+        assert!(directed.iter().all(|edge| union.contains(edge)));
+        assert!(mutual.len() < directed.len() && directed.len() < union.len());
+
+        for (_, neighbors) in node.knn_graph_symmetric(4, Symmetry::Union) {
+            assert!(neighbors
+                .windows(2)
+                .all(|pair| pair[0].distance <= pair[1].distance));
+        }
+    }
+
+    #[test]
+    fn test_knn_join() {
+        let queries: Vec<Point<f64>> = random_points(300, 9);
+        let reference: Vec<Point<f64>> = random_points(700, 10);
+        let query_node: KdNode<f64> = KdNode::build(queries.clone());
+        let reference_node: KdNode<f64> = KdNode::build(reference.clone());
+
+        for k in [1, 6] {
+            let joined = query_node.knn_join(&reference_node, k);
+            assert_eq!(joined.len(), queries.len());
+            for (point, neighbors) in &joined {
+                let mut expected: Vec<f64> = reference
+                    .iter()
+                    .map(|other| point::distance(point, other))
+                    .collect();
+                expected.sort_by(f64::total_cmp);
+                expected.truncate(k);
+                assert_eq!(
+                    neighbors
+                        .iter()
+                        .map(|neighbor| neighbor.distance)
+                        .collect::<Vec<_>>(),
+                    expected
+                );
+            }
+            #[cfg(feature = "rayon")]
+            assert_eq!(query_node.par_knn_join(&reference_node, k), joined);
+        }
+
+        // A point in both trees is its own nearest neighbor across the join.
+        let joined = reference_node.knn_join(&reference_node, 1);
+        assert!(joined
+            .iter()
+            .all(|(point, neighbors)| neighbors[0].point == *point));
+        assert!(query_node
+            .knn_join(&KdNode::new(), 3)
+            .iter()
+            .all(|(_, neighbors)| neighbors.is_empty()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Radius must be positive")]
+    fn test_radius_join_negative_radius() {
+        let node: KdNode<f64> = KdNode::build(TEST_POINTS_F64.to_vec());
+        node.radius_join(&node, -1.);
+    }
+
+    #[test]
+    fn test_radius_join() {
+        let a: Vec<Point<f64>> = random_points(400, 11);
+        let b: Vec<Point<f64>> = random_points(500, 12);
+        let a_node: KdNode<f64> = KdNode::build(a.clone());
+        let b_node: KdNode<f64> = KdNode::build(b.clone());
+
+        for radius in [0.5, 3., 25.] {
+            let mut expected: Vec<(Point<f64>, Point<f64>, f64)> = Vec::new();
+            for p in &a {
+                for q in &b {
+                    let dis: f64 = point::distance(p, q);
+                    if dis <= radius {
+                        expected.push((*p, *q, dis));
+                    }
+                }
+            }
+            let key =
+                |pair: &(Point<f64>, Point<f64>, f64)| (pair.0.x, pair.0.y, pair.1.x, pair.1.y);
+            expected.sort_by(|l, r| key(l).partial_cmp(&key(r)).unwrap());
+
+            let mut joined = a_node.radius_join(&b_node, radius);
+            joined.sort_by(|l, r| key(l).partial_cmp(&key(r)).unwrap());
+            assert_eq!(joined, expected);
+
+            #[cfg(feature = "rayon")]
+            {
+                let mut joined = a_node.par_radius_join(&b_node, radius);
+                joined.sort_by(|l, r| key(l).partial_cmp(&key(r)).unwrap());
+                assert_eq!(joined, expected);
+            }
+        }
+
+        assert_eq!(a_node.radius_join(&KdNode::new(), 10.), vec![]);
+    }
+
+    #[test]
+    fn test_closest_pair() {
+        for (n, seed) in [(2, 13), (50, 14), (3_000, 15)] {
+            let points: Vec<Point<f64>> = random_points(n, seed);
+            let node: KdNode<f64> = KdNode::build(points.clone());
+            let (a, b, dis) = node.closest_pair().unwrap();
+            let expected: f64 = brute_force_pairs(&points, f64::INFINITY)
+                .iter()
+                .map(|pair| pair.2)
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(dis, expected);
+            assert_eq!(point::distance(&a, &b), dis);
+        }
+
+        let mut points: Vec<Point<i32>> = TEST_POINTS_I32.to_vec();
+        assert_eq!(
+            KdNode::build(points.clone()).closest_pair().unwrap().2,
+            2f64.sqrt()
+        );
+        points.push(Point { x: 7, y: 3 });
+        assert_eq!(
+            KdNode::build(points).closest_pair(),
+            Some((Point { x: 7, y: 3 }, Point { x: 7, y: 3 }, 0.))
+        );
+        assert_eq!(
+            KdNode::build(vec![Point { x: 1, y: 1 }]).closest_pair(),
+            None
+        );
+        assert_eq!(KdNode::<i32>::new().closest_pair(), None);
+    }
+
+    #[test]
+    fn test_closest_pair_between() {
+        let a: Vec<Point<f64>> = random_points(800, 16);
+        let b: Vec<Point<f64>> = random_points(1_200, 17);
+        let a_node: KdNode<f64> = KdNode::build(a.clone());
+        let b_node: KdNode<f64> = KdNode::build(b.clone());
+
+        let (p, q, dis) = a_node.closest_pair_between(&b_node).unwrap();
+        let expected: f64 = a
+            .iter()
+            .flat_map(|p| b.iter().map(move |q| point::distance(p, q)))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(dis, expected);
+        assert!(a.contains(&p) && b.contains(&q));
+        assert_eq!(point::distance(&p, &q), dis);
+
+        assert_eq!(a_node.closest_pair_between(&a_node).unwrap().2, 0.);
+        assert_eq!(a_node.closest_pair_between(&KdNode::new()), None);
+    }
+
+    #[test]
+    fn test_k_farthest() {
+        let points: Vec<Point<f64>> = random_points(2_000, 18);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        for origin in
+            [Point { x: 50., y: 50. }, Point { x: 3., y: 97. }, Point { x: -400., y: 20. }]
+        {
+            let mut expected: Vec<f64> = points
+                .iter()
+                .map(|point| point::distance(&origin, point))
+                .collect();
+            expected.sort_by(|a, b| b.total_cmp(a));
+            for k in [1, 5, 60] {
+                let farthest = node.k_farthest(origin, k);
+                assert_eq!(
+                    farthest
+                        .iter()
+                        .map(|neighbor| neighbor.distance)
+                        .collect::<Vec<_>>(),
+                    expected[..k]
+                );
+                assert!(
+                    farthest
+                        .iter()
+                        .all(|neighbor| point::distance(&origin, &neighbor.point)
+                            == neighbor.distance)
+                );
+            }
+            assert_eq!(node.farthest(origin).unwrap().distance, expected[0]);
+        }
+
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        assert_eq!(
+            node.farthest(Point { x: 0, y: 0 }).unwrap().point,
+            Point { x: 9, y: 9 }
+        );
+        assert_eq!(node.k_farthest(Point { x: 0, y: 0 }, 20).len(), 8);
+        assert_eq!(node.k_farthest(Point { x: 0, y: 0 }, 0), vec![]);
+        assert_eq!(KdNode::<i32>::new().farthest(Point { x: 0, y: 0 }), None);
+    }
+
+    #[test]
+    fn test_reverse_nearest_neighbors() {
+        let points: Vec<Point<f64>> = random_points(1_500, 19);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        for origin in [
+            Point { x: 50., y: 50. },
+            Point { x: 0.5, y: 99. },
+            Point { x: -30., y: 40. },
+            points[7],
+        ] {
+            for k in [1, 4, 25] {
+                let mut expected: Vec<f64> = Vec::new();
+                for (i, point) in points.iter().enumerate() {
+                    let to_origin: f64 = point::distance(point, &origin);
+                    let closer: usize = points
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, other)| *j != i && point::distance(point, other) < to_origin)
+                        .count();
+                    if closer < k {
+                        expected.push(to_origin);
+                    }
+                }
+                expected.sort_by(f64::total_cmp);
+
+                let reverse = node.reverse_nearest_neighbors(origin, k);
+                assert_eq!(
+                    reverse
+                        .iter()
+                        .map(|neighbor| neighbor.distance)
+                        .collect::<Vec<_>>(),
+                    expected
+                );
+                assert!(
+                    reverse
+                        .iter()
+                        .all(|neighbor| point::distance(&origin, &neighbor.point)
+                            == neighbor.distance)
+                );
+            }
+        }
+
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        assert_eq!(
+            node.reverse_nearest_neighbors(Point { x: 0, y: 0 }, 0),
+            vec![]
+        );
+        assert_eq!(
+            KdNode::<i32>::new().reverse_nearest_neighbors(Point { x: 0, y: 0 }, 3),
+            vec![]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Radius must be positive")]
+    fn test_count_within_radius_negative_radius() {
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        node.count_within_radius(Point { x: 0, y: 0 }, -1.);
+    }
+
+    #[test]
+    fn test_count_within_radius() {
+        let points: Vec<Point<f64>> = random_points(3_000, 20);
+        let node: KdNode<f64> = KdNode::build(points.clone());
+        for origin in [
+            Point { x: 50., y: 50. },
+            Point { x: 0., y: 100. },
+            Point { x: -20., y: 50. },
+            points[11],
+        ] {
+            for radius in [0., 0.5, 4., 30., 200.] {
+                let expected: usize = points
+                    .iter()
+                    .filter(|point| point::distance(&origin, point) <= radius)
+                    .count();
+                assert_eq!(node.count_within_radius(origin, radius), expected);
+                assert_eq!(node.any_within_radius(origin, radius), expected > 0);
+                assert_eq!(node.nearest_neighbor(origin, radius).len(), expected);
+            }
+        }
+
+        let node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        assert_eq!(node.count_within_radius(Point { x: 7, y: 3 }, 0.), 1);
+        assert!(node.any_within_radius(Point { x: 7, y: 3 }, 0.));
+        assert!(!node.any_within_radius(Point { x: 5, y: 5 }, 0.9));
+        assert_eq!(node.count_within_radius(Point { x: 0, y: 0 }, 100.), 8);
+        assert!(!KdNode::<i32>::new().any_within_radius(Point { x: 0, y: 0 }, 100.));
+    }
+
+    /// Check every stored box is the tight box around its subtree and every size is right.
+    fn check_bounds(node: &KdNode<f64>) {
+        let mut stack: Vec<&KdNode<f64>> = vec![node];
+        while let Some(node) = stack.pop() {
+            if let Node {
+                left,
+                right,
+                size,
+                bounds,
+                ..
+            } = node
+            {
+                let points: Vec<Point<f64>> = node.iter().copied().collect();
+                assert_eq!(*size, points.len());
+                assert_eq!(bounds.as_ref(), Some(&Bounds::of(&points)));
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let points: Vec<Point<f64>> = random_points(2_000, 21);
+        let mut node: KdNode<f64> = KdNode::build_with_bounds(points[..1_000].to_vec());
+        check_bounds(&node);
+        assert_eq!(KdNode::build(points.clone()).bounds(), None);
+
+        for point in &points[1_000..] {
+            node.insert_point(*point);
+        }
+        check_bounds(&node);
+
+        // Remove the points from around the edge of the box so it shrinks.
+        let mut remaining: Vec<Point<f64>> = points.clone();
+        remaining.sort_by(|a, b| {
+            (a.x - 50.)
+                .abs()
+                .max((a.y - 50.).abs())
+                .total_cmp(&(b.x - 50.).abs().max((b.y - 50.).abs()))
+        });
+        for point in remaining.split_off(1_200).into_iter().rev() {
+            assert!(node.remove_point(point));
+        }
+        check_bounds(&node);
+        assert_eq!(node.bounds(), Some(&Bounds::of(&remaining)));
+
+        for origin in [Point { x: 50., y: 50. }, Point { x: 120., y: -3. }] {
+            for radius in [0.5, 6., 40.] {
+                let mut expected: Vec<f64> = remaining
+                    .iter()
+                    .map(|point| point::distance(&origin, point))
+                    .filter(|dis| *dis <= radius)
+                    .collect();
+                expected.sort_by(f64::total_cmp);
+                let found: Vec<f64> = node
+                    .nearest_neighbor_with_distance(origin, radius)
+                    .iter()
+                    .map(|neighbor| neighbor.distance)
+                    .collect();
+                assert_eq!(found, expected);
+                assert_eq!(node.count_within_radius(origin, radius), expected.len());
+            }
+            let mut expected: Vec<f64> = remaining
+                .iter()
+                .map(|point| point::distance(&origin, point))
+                .collect();
+            expected.sort_by(f64::total_cmp);
+            let nearest: Vec<f64> = node
+                .n_nearest_neighbor_with_distance(origin, 10)
+                .iter()
+                .map(|neighbor| neighbor.distance)
+                .collect();
+            assert_eq!(nearest, expected[..10]);
+            assert_eq!(
+                node.farthest(origin).unwrap().distance,
+                expected[expected.len() - 1]
+            );
+
+            let unbounded: KdNode<f64> = KdNode::build(remaining.clone());
+            let distances = |reverse: Vec<Neighbor<Point<f64>>>| {
+                reverse
+                    .iter()
+                    .map(|neighbor| neighbor.distance)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                distances(node.reverse_nearest_neighbors(origin, 3)),
+                distances(unbounded.reverse_nearest_neighbors(origin, 3))
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_bounds() {
+        assert_eq!(KdNode::<f64>::build_with_bounds(vec![]), KdNode::new());
+        let mut node: KdNode<f64> = KdNode::build_with_bounds(vec![]);
+        node.insert(1., 2.);
+        assert_eq!(node.bounds(), Some(&Bounds::of([&Point { x: 1., y: 2. }])));
+
+        // Emptying a bounded tree keeps it bounded.
+        assert!(node.remove_point(Point { x: 1., y: 2. }));
+        assert_eq!(node, Empty);
+        node.insert(3., 4.);
+        node.insert(5., 0.);
+        check_bounds(&node);
+    }
+
+    #[test]
+    fn test_remove_point() {
+        let mut node: KdNode<i32> = KdNode::build(TEST_POINTS_I32.to_vec());
+        assert!(!node.remove(5, 5));
+        assert!(node.remove(4, 9));
+        assert_eq!(node.len(), 7);
+        assert_eq!(node.nearest_neighbor_x_y(4, 9, 0.), vec![]);
+        assert!(!node.remove(4, 9));
+
+        // Points stored twice are removed one at a time.
+        node.insert(7, 3);
+        assert!(node.remove(7, 3));
+        assert_eq!(
+            node.nearest_neighbor_x_y(7, 3, 0.),
+            vec![Point { x: 7, y: 3 }]
+        );
+
+        let points: Vec<Point<f64>> = random_points(3_000, 22);
+        let mut node: KdNode<f64> = KdNode::build(points.clone());
+        let (removed, kept) = points.split_at(2_500);
+        for point in removed {
+            assert!(node.remove_point(*point));
+        }
+        assert_eq!(node.len(), kept.len());
+        assert!(
+            height(&node) <= 3 * (kept.len() as f64).log2().ceil() as usize,
+            "{}",
+            height(&node)
+        );
+
+        let mut expected: Vec<Point<f64>> = kept.to_vec();
+        let mut found: Vec<Point<f64>> = node.iter().copied().collect();
+        expected.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        found.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        assert_eq!(found, expected);
+
+        for point in kept {
+            assert!(node.remove_point(*point));
+        }
+        assert!(node.is_empty());
+    }
+
+    #[test]
+    fn test_query_region() {
+        use crate::region::*;
+
+        let points: Vec<Point<f64>> = random_points(3_000, 23);
+        let regions: Vec<Box<dyn Region<Point<f64>>>> = vec![
+            Box::new(ConvexPolygon::new(vec![
+                [10., 10.],
+                [80., 20.],
+                [60., 70.],
+                [15., 50.],
+            ])),
+            // The same polygon in clockwise order.
+            Box::new(ConvexPolygon::new(vec![
+                [15., 50.],
+                [60., 70.],
+                [80., 20.],
+                [10., 10.],
+            ])),
+            // A U shape, concave around the gap between its arms.
+            Box::new(Polygon::new(vec![
+                [5., 5.],
+                [95., 5.],
+                [95., 90.],
+                [70., 90.],
+                [70., 30.],
+                [30., 30.],
+                [30., 90.],
+                [5., 90.],
+            ])),
+            // A star whose edges cross tree splits at every angle.
+            Box::new(Polygon::new(
+                (0..10)
+                    .map(|i| {
+                        let angle: f64 = i as f64 * std::f64::consts::PI / 5.;
+                        let radius: f64 = if i % 2 == 0 { 45. } else { 15. };
+                        [50. + radius * angle.cos(), 50. + radius * angle.sin()]
+                    })
+                    .collect(),
+            )),
+            Box::new(Circle::new([30., 60.], 25.)),
+            Box::new(Circle::new([-10., 50.], 5.)),
+            Box::new(Annulus::new([50., 50.], 20., 35.)),
+            Box::new(HalfPlane::new([1., 2.], 120.)),
+            Box::new(HalfPlane::left_of([0., 0.], [100., 100.])),
+        ];
+
+        let sorted = |mut points: Vec<Point<f64>>| {
+            points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+            points
+        };
+        for node in [KdNode::build(points.clone()), KdNode::build_with_bounds(points.clone())] {
+            for region in &regions {
+                let expected: Vec<Point<f64>> = points
+                    .iter()
+                    .copied()
+                    .filter(|point| region.contains_point(point))
+                    .collect();
+                assert_eq!(sorted(node.query_region(region.as_ref())), sorted(expected));
+            }
+        }
+
+        let u_shape = Polygon::new(vec![
+            [0., 0.],
+            [3., 0.],
+            [3., 3.],
+            [2., 3.],
+            [2., 1.],
+            [1., 1.],
+            [1., 3.],
+            [0., 3.],
+        ]);
+        assert!(Region::<Point<f64>>::contains_point(
+            &u_shape,
+            &Point { x: 0.5, y: 2. }
+        ));
+        assert!(Region::<Point<f64>>::contains_point(
+            &u_shape,
+            &Point { x: 1.5, y: 1. }
+        ));
+        assert!(!Region::<Point<f64>>::contains_point(
+            &u_shape,
+            &Point { x: 1.5, y: 2. }
+        ));
+        assert!(!Region::<Point<f64>>::intersects_box(
+            &u_shape,
+            &[1.2, 1.5],
+            &[1.8, 2.5]
+        ));
+        assert!(Region::<Point<f64>>::contains_box(
+            &u_shape,
+            &[0.2, 0.2],
+            &[2.8, 0.8]
+        ));
+        assert!(!Region::<Point<f64>>::contains_box(
+            &u_shape,
+            &[0.2, 0.2],
+            &[2.8, 2.]
+        ));
+    }
+
+    /// Distance from the point to the line `from + t * (to - from)` for `t` between zero and `end`, and that `t`
+    fn brute_force_line(
+        point: &Point<f64>,
+        from: &Point<f64>,
+        to: &Point<f64>,
+        end: f64,
+    ) -> (f64, f64) {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let t: f64 = (((point.x - from.x) * dx + (point.y - from.y) * dy) / (dx * dx + dy * dy))
+            .clamp(0., end);
+        (
+            (point.x - from.x - t * dx).hypot(point.y - from.y - t * dy),
+            t,
+        )
+    }
+
+    #[test]
+    fn test_segment_and_ray_neighbors() {
+        let points: Vec<Point<f64>> = random_points(3_000, 24);
+        let lines = [
+            (Point { x: 10., y: 10. }, Point { x: 90., y: 70. }),
+            (Point { x: 50., y: -20. }, Point { x: 50., y: 40. }),
+            (Point { x: 120., y: 30. }, Point { x: 110., y: 31. }),
+            (Point { x: 40., y: 40. }, Point { x: 40., y: 40.5 }),
+        ];
+        for node in [KdNode::build(points.clone()), KdNode::build_with_bounds(points.clone())] {
+            for (from, to) in lines {
+                for distance in [0.5, 3., 15.] {
+                    for (end, found) in [
+                        (1., node.segment_neighbors(from, to, distance)),
+                        (f64::INFINITY, node.ray_neighbors(from, to, distance)),
+                    ] {
+                        let mut expected: Vec<f64> = points
+                            .iter()
+                            .map(|point| brute_force_line(point, &from, &to, end).0)
+                            .filter(|dis| *dis <= distance)
+                            .collect();
+                        expected.sort_by(f64::total_cmp);
+                        assert_eq!(found.len(), expected.len());
+                        for (neighbor, dis) in found.iter().zip(expected) {
+                            assert!((neighbor.distance - dis).abs() < 1e-9);
+                        }
+                    }
+
+                    let length: f64 = point::distance(&from, &to);
+                    let expected: Option<f64> = points
+                        .iter()
+                        .map(|point| brute_force_line(point, &from, &to, f64::INFINITY))
+                        .filter(|(dis, _)| *dis <= distance)
+                        .map(|(_, t)| t * length)
+                        .min_by(f64::total_cmp);
+                    let hit = node.ray_hit(from, to, distance);
+                    assert_eq!(hit.is_some(), expected.is_some());
+                    if let (Some(hit), Some(expected)) = (hit, expected) {
+                        assert!((hit.distance - expected).abs() < 1e-9);
+                        assert!(
+                            brute_force_line(&hit.point, &from, &to, f64::INFINITY).0 <= distance
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            KdNode::<f64>::new().ray_hit(Point { x: 0., y: 0. }, Point { x: 1., y: 0. }, 1.),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dbscan() {
+        use crate::cluster::{dbscan, Label};
+
+        // Three square blobs of 400 points, six points far from everything and two lone points between blobs.
+        let centers =
+            [Point { x: 20., y: 20. }, Point { x: 80., y: 30. }, Point { x: 40., y: 85. }];
+        let mut points: Vec<Point<f64>> = Vec::new();
+        for (i, center) in centers.iter().enumerate() {
+            let blob = random_points(400, 25 + i as u64);
+            points.extend(blob.iter().map(|point| Point {
+                x: center.x + point.x / 10. - 5.,
+                y: center.y + point.y / 10. - 5.,
+            }));
+        }
+        let outliers: Vec<Point<f64>> = vec![
+            Point { x: 200., y: 0. },
+            Point { x: 200., y: 100. },
+            Point { x: -100., y: 50. },
+            Point { x: 50., y: 50. },
+            Point { x: 60., y: 60. },
+            Point { x: 0., y: 100. },
+        ];
+        points.extend(outliers.iter().copied());
+
+        let labels: Vec<Label> = dbscan(&KdNode::build_indexed(points.clone()), 2., 5);
+        assert_eq!(labels.len(), points.len());
+        for blob in 0..3 {
+            assert!(labels[blob * 400..(blob + 1) * 400]
+                .iter()
+                .all(|label| *label == Label::Cluster(blob)));
+        }
+        assert!(labels[1_200..].iter().all(|label| *label == Label::Noise));
+
+        // Points on the edge of a cluster join it without extending it further.
+        let line: Vec<Point<f64>> = (0..5)
+            .map(|x| Point { x: x as f64, y: 0. })
+            .chain([Point { x: 5.4, y: 0. }, Point { x: 7., y: 0. }])
+            .collect();
+        let labels: Vec<Label> = dbscan(&KdNode::build_indexed(line), 1.5, 3);
+        assert_eq!(
+            labels,
+            [vec![Label::Cluster(0); 6], vec![Label::Noise]].concat()
+        );
+
+        assert_eq!(
+            dbscan(&KdNode::build_indexed(points.clone()), 2., 1_000),
+            vec![Label::Noise; points.len()]
+        );
+        assert_eq!(
+            dbscan(&KdNode::<f64>::build_indexed(Vec::new()), 2., 5),
+            vec![]
+        );
+    }
+
+    /// Lloyd's algorithm comparing every point with every centroid
+    fn brute_force_kmeans(
+        points: &[Point<f64>],
+        mut centroids: Vec<Vec<f64>>,
+        max_iterations: usize,
+    ) -> (Vec<Vec<f64>>, Vec<usize>) {
+        let closest = |point: &Point<f64>, centroids: &[Vec<f64>]| {
+            let distances = centroids
+                .iter()
+                .map(|centroid| (point.x - centroid[0]).powi(2) + (point.y - centroid[1]).powi(2));
+            distances
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap()
+                .0
+        };
+        for _ in 0..max_iterations {
+            let mut totals: Vec<(f64, f64, usize)> = vec![(0., 0., 0); centroids.len()];
+            for point in points {
+                let total = &mut totals[closest(point, &centroids)];
+                *total = (total.0 + point.x, total.1 + point.y, total.2 + 1);
+            }
+            for (centroid, (x, y, count)) in centroids.iter_mut().zip(totals) {
+                if count > 0 {
+                    *centroid = vec![x / count as f64, y / count as f64];
+                }
+            }
+        }
+        let assignments: Vec<usize> = points
+            .iter()
+            .map(|point| closest(point, &centroids))
+            .collect();
+        (centroids, assignments)
+    }
+
+    #[test]
+    fn test_kmeans() {
+        use crate::cluster::{kmeans, Init, KMeans};
+
+        let points: Vec<Point<f64>> = random_points(5_000, 26);
+        let tree = KdNode::build_indexed(points.clone());
+        let init: Vec<Vec<f64>> = points[..12]
+            .iter()
+            .map(|point| vec![point.x, point.y])
+            .collect();
+        for max_iterations in [1, 5, 30] {
+            let clusters: KMeans = kmeans(&tree, Init::Centroids(init.clone()), max_iterations);
+            let (centroids, assignments) =
+                brute_force_kmeans(&points, init.clone(), clusters.iterations);
+            assert!(clusters.iterations <= max_iterations);
+            assert_eq!(clusters.assignments, assignments);
+            for (found, expected) in clusters.centroids.iter().zip(&centroids) {
+                assert!(
+                    (found[0] - expected[0]).abs() < 1e-9 && (found[1] - expected[1]).abs() < 1e-9
+                );
+            }
+            let inertia: f64 = points
+                .iter()
+                .zip(&assignments)
+                .map(|(point, cluster)| {
+                    (point.x - centroids[*cluster][0]).powi(2)
+                        + (point.y - centroids[*cluster][1]).powi(2)
+                })
+                .sum();
+            assert!((clusters.inertia - inertia).abs() < 1e-6 * inertia);
+        }
+
+        // k-means++ puts a centroid in each of four far apart blobs.
+        let centers = [(10., 10.), (90., 10.), (10., 90.), (90., 90.)];
+        let blobs: Vec<Point<f64>> = centers
+            .iter()
+            .zip(27..)
+            .flat_map(|((x, y), seed)| {
+                random_points(250, seed)
+                    .into_iter()
+                    .map(move |point| Point {
+                        x: x + point.x / 20.,
+                        y: y + point.y / 20.,
+                    })
+            })
+            .collect();
+        let clusters: KMeans = kmeans(
+            &KdNode::build_indexed(blobs.clone()),
+            Init::PlusPlus { k: 4, seed: 3 },
+            100,
+        );
+        for blob in clusters.assignments.chunks(250) {
+            assert!(blob.iter().all(|cluster| *cluster == blob[0]));
+        }
+        let mut seen: Vec<usize> = clusters.assignments.iter().step_by(250).copied().collect();
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+        assert_eq!(
+            clusters,
+            kmeans(
+                &KdNode::build_indexed(blobs),
+                Init::PlusPlus { k: 4, seed: 3 },
+                100
+            )
+        );
+
+        let empty: KMeans = kmeans(
+            &KdNode::<f64>::build_indexed(Vec::new()),
+            Init::PlusPlus { k: 3, seed: 0 },
+            10,
+        );
+        assert_eq!(
+            (
+                empty.centroids.len(),
+                empty.assignments.len(),
+                empty.inertia
+            ),
+            (0, 0, 0.)
+        );
+    }
+
+    fn brute_force_mst_weight(points: &[Point<f64>]) -> f64 {
+        let dis =
+            |a: &Point<f64>, b: &Point<f64>| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+        let mut reached: Vec<bool> = vec![false; points.len()];
+        let mut nearest: Vec<f64> = vec![f64::INFINITY; points.len()];
+        let mut total: f64 = 0.;
+        let mut next: usize = 0;
+        for _ in 0..points.len() {
+            reached[next] = true;
+            total += if nearest[next].is_finite() {
+                nearest[next]
+            } else {
+                0.
+            };
+            for (i, point) in points.iter().enumerate() {
+                nearest[i] = nearest[i].min(dis(&points[next], point));
+            }
+            next = (0..points.len())
+                .filter(|i| !reached[*i])
+                .min_by(|a, b| nearest[*a].total_cmp(&nearest[*b]))
+                .unwrap_or(0);
+        }
+        total
+    }
+
+    #[test]
+    fn test_emst() {
+        // Grid points have many edges of the same length, stored twice some are zero.
+        let grid: Vec<Point<f64>> = (0..400)
+            .map(|i| Point {
+                x: (i % 20) as f64,
+                y: ((i / 20) % 15) as f64,
+            })
+            .collect();
+        for points in [random_points(700, 31), random_points(3, 32), grid] {
+            let edges = KdNode::build(points.clone()).emst();
+            assert_eq!(edges.len(), points.len() - 1);
+            assert!(edges.windows(2).all(|pair| pair[0].2 <= pair[1].2));
+            let total: f64 = edges.iter().map(|edge| edge.2).sum();
+            let expected: f64 = brute_force_mst_weight(&points);
+            assert!((total - expected).abs() < 1e-9 * expected.max(1.));
+
+            // The edges join every point, so they can't hold a cycle either.
+            let mut groups: Vec<usize> = (0..points.len()).collect();
+            fn find(groups: &mut Vec<usize>, i: usize) -> usize {
+                if groups[i] != i {
+                    groups[i] = find(groups, groups[i]);
+                }
+                groups[i]
+            }
+            for (a, b, dis) in &edges {
+                assert_eq!(*dis, ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt());
+                let (a, b) = (
+                    points.iter().position(|p| p == a).unwrap(),
+                    points.iter().position(|p| p == b).unwrap(),
+                );
+                let (a, b) = (find(&mut groups, a), find(&mut groups, b));
+                groups[a] = b;
+            }
+            // Points stored twice are both found at their first index.
+            let root: usize = find(&mut groups, 0);
+            assert!(points.iter().all(|point| find(
+                &mut groups,
+                points.iter().position(|p| p == point).unwrap()
+            ) == root));
+        }
+        assert!(KdNode::build(vec![Point { x: 1., y: 1. }])
+            .emst()
+            .is_empty());
+        assert!(KdNode::<f64>::build(Vec::new()).emst().is_empty());
+    }
+
+    #[test]
+    fn test_single_linkage() {
+        use crate::cluster::{single_linkage, Dendrogram};
+
+        let points: Vec<Point<f64>> = random_points(500, 33);
+        let dendrogram: Dendrogram = single_linkage(&KdNode::build_indexed(points.clone()));
+        assert_eq!((dendrogram.len, dendrogram.merges.len()), (500, 499));
+        assert_eq!(dendrogram.merges.last().unwrap().size, 500);
+        assert!(dendrogram
+            .merges
+            .windows(2)
+            .all(|pair| pair[0].distance <= pair[1].distance));
+
+        // Cutting at a distance leaves the groups of points joined by chains of shorter steps.
+        for distance in [0.5, 2., 4., 8.] {
+            let mut expected: Vec<usize> = (0..points.len()).collect();
+            for (i, a) in points.iter().enumerate() {
+                for (j, b) in points.iter().enumerate() {
+                    if ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt() <= distance {
+                        let (from, to) =
+                            (expected[i].max(expected[j]), expected[i].min(expected[j]));
+                        expected
+                            .iter_mut()
+                            .filter(|group| **group == from)
+                            .for_each(|group| *group = to);
+                    }
+                }
+            }
+            let labels: Vec<usize> = dendrogram.cut(distance);
+            for i in 0..points.len() {
+                for j in 0..points.len() {
+                    assert_eq!(labels[i] == labels[j], expected[i] == expected[j]);
+                }
+            }
+        }
+        assert!(dendrogram.cut(1000.).iter().all(|label| *label == 0));
+        assert_eq!(dendrogram.cut(-1.), (0..500).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_k_distance_and_lof() {
+        use crate::density::{k_distance, lof};
+
+        let dis =
+            |a: &Point<f64>, b: &Point<f64>| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+        let mut points: Vec<Point<f64>> = random_points(300, 34);
+        points.extend([Point { x: 200., y: 200. }, Point { x: 5., y: 5. }, Point { x: 5., y: 5. }]);
+        let tree = KdNode::build_indexed(points.clone());
+        for k in [1, 4, 10] {
+            // Ties can't be told apart, so only the distances of the neighbors are compared.
+            let neighbors: Vec<Vec<(usize, f64)>> = points
+                .iter()
+                .enumerate()
+                .map(|(i, point)| {
+                    let mut others: Vec<(usize, f64)> = points
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(j, other)| (j, dis(point, other)))
+                        .collect();
+                    others.sort_by(|a, b| a.1.total_cmp(&b.1));
+                    others.truncate(k);
+                    others
+                })
+                .collect();
+            let distances: Vec<f64> = neighbors.iter().map(|others| others[k - 1].1).collect();
+            assert_eq!(k_distance(&tree, k), distances);
+
+            let densities: Vec<f64> = neighbors
+                .iter()
+                .map(|others| {
+                    k as f64
+                        / others
+                            .iter()
+                            .map(|(j, d)| d.max(distances[*j]))
+                            .sum::<f64>()
+                })
+                .collect();
+            let expected: Vec<f64> = neighbors
+                .iter()
+                .zip(&densities)
+                .map(|(others, density)| {
+                    others.iter().map(|(j, _)| densities[*j]).sum::<f64>() / k as f64 / density
+                })
+                .collect();
+            let scores: Vec<f64> = lof(&tree, k);
+            for (i, (score, expected)) in scores.iter().zip(&expected).enumerate() {
+                // The two points stored twice are each other's only neighbor at k = 1.
+                if k == 1 && i >= 301 {
+                    assert_eq!(*score, 1.);
+                } else if !(points[i] == Point { x: 5., y: 5. }) {
+                    assert!(score == expected || (score - expected).abs() < 1e-9 * expected);
+                }
+            }
+            assert!(scores[300] > 3.);
+        }
+
+        assert_eq!(
+            k_distance(&KdNode::build_indexed(vec![Point { x: 1., y: 1. }]), 3),
+            vec![f64::INFINITY]
+        );
+        assert_eq!(
+            lof(&KdNode::build_indexed(vec![Point { x: 1., y: 1. }]), 3),
+            vec![1.]
+        );
+        assert_eq!(
+            k_distance(
+                &KdNode::build_indexed(vec![Point { x: 1., y: 1. }, Point { x: 1., y: 4. }]),
+                3
+            ),
+            vec![3., 3.]
+        );
+    }
+
+    #[test]
+    fn test_kernel_density() {
+        use crate::density::Kernel;
+
+        let points: Vec<Point<f64>> = random_points(400, 35);
+        let tree: KdNode<f64> = KdNode::build(points.clone());
+        for (kernel, bandwidth) in
+            [(Kernel::Epanechnikov, 6.), (Kernel::Gaussian { cutoff: 4. }, 3.)]
+        {
+            // The density integrates to about one over the plane.
+            let mut total: f64 = 0.;
+            for x in -30..130 {
+                for y in -30..130 {
+                    total += tree.kernel_density(
+                        Point {
+                            x: x as f64 + 0.5,
+                            y: y as f64 + 0.5,
+                        },
+                        kernel,
+                        bandwidth,
+                    );
+                }
+            }
+            assert!((total - 1.).abs() < 2e-3, "{total}");
+
+            for origin in random_points(20, 36) {
+                let expected: f64 = points
+                    .iter()
+                    .map(|point| {
+                        let u: f64 = ((point.x - origin.x).powi(2) + (point.y - origin.y).powi(2))
+                            .sqrt()
+                            / bandwidth;
+                        match kernel {
+                            Kernel::Epanechnikov => {
+                                2. / std::f64::consts::PI * (1. - u * u).max(0.)
+                            }
+                            Kernel::Gaussian { cutoff } if u <= cutoff => {
+                                (-u * u / 2.).exp() / (2. * std::f64::consts::PI)
+                            }
+                            Kernel::Gaussian { .. } => 0.,
+                        }
+                    })
+                    .sum::<f64>()
+                    / (400. * bandwidth * bandwidth);
+                assert!((tree.kernel_density(origin, kernel, bandwidth) - expected).abs() < 1e-12);
+            }
+        }
+        assert_eq!(
+            KdNode::<f64>::build(Vec::new()).kernel_density(
+                Point { x: 0., y: 0. },
+                Kernel::Epanechnikov,
+                1.
+            ),
+            0.
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        use crate::interpolate::{Grid, Interpolation, Neighbors};
+        use crate::point::Item;
+
+        let points: Vec<Point<f64>> = random_points(500, 37);
+        let items: Vec<Item<Point<f64>, f64>> = points
+            .iter()
+            .map(|point| Item::new(*point, point.x * 0.5 - point.y))
+            .collect();
+        let tree = KdNode::build(items.clone());
+
+        let origins: Vec<Point<f64>> = random_points(50, 38);
+        for origin in &origins {
+            let mut near: Vec<(f64, f64)> = items
+                .iter()
+                .map(|item| {
+                    (
+                        ((item.point.x - origin.x).powi(2) + (item.point.y - origin.y).powi(2))
+                            .sqrt(),
+                        item.data,
+                    )
+                })
+                .collect();
+            near.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let idw = |near: &[(f64, f64)], power: f64| {
+                near.iter().map(|(d, v)| v / d.powf(power)).sum::<f64>()
+                    / near.iter().map(|(d, _)| 1. / d.powf(power)).sum::<f64>()
+            };
+
+            let found = Interpolation::InverseDistance {
+                power: 2.,
+                neighbors: Neighbors::Nearest(6),
+            }
+            .evaluate(&tree, *origin)
+            .unwrap();
+            assert!((found - idw(&near[..6], 2.)).abs() < 1e-9);
+            let within: Vec<(f64, f64)> = near.iter().copied().filter(|(d, _)| *d <= 8.).collect();
+            let found = Interpolation::InverseDistance {
+                power: 1.5,
+                neighbors: Neighbors::Within(8.),
+            }
+            .evaluate(&tree, *origin);
+            match found {
+                Some(found) => assert!((found - idw(&within, 1.5)).abs() < 1e-9),
+                None => assert!(within.is_empty()),
+            }
+
+            let found = Interpolation::Mean { k: 5 }
+                .evaluate(&tree, *origin)
+                .unwrap();
+            assert!((found - near[..5].iter().map(|(_, v)| v).sum::<f64>() / 5.).abs() < 1e-9);
+            for k in [4, 5] {
+                let mut values: Vec<f64> = near[..k].iter().map(|(_, v)| *v).collect();
+                values.sort_by(f64::total_cmp);
+                let median: f64 = if k % 2 == 1 {
+                    values[k / 2]
+                } else {
+                    (values[k / 2 - 1] + values[k / 2]) / 2.
+                };
+                assert_eq!(
+                    Interpolation::Median { k }.evaluate(&tree, *origin),
+                    Some(median)
+                );
+            }
+        }
+
+        // A point at the origin gives its own value.
+        let idw = Interpolation::InverseDistance {
+            power: 2.,
+            neighbors: Neighbors::Nearest(4),
+        };
+        assert_eq!(idw.evaluate(&tree, items[7].point), Some(items[7].data));
+        assert_eq!(
+            Interpolation::InverseDistance {
+                power: 2.,
+                neighbors: Neighbors::Within(1.)
+            }
+            .evaluate(&tree, Point { x: 500., y: 500. }),
+            None
+        );
+        assert_eq!(
+            Interpolation::Mean { k: 3 }.evaluate(
+                &KdNode::build(Vec::<Item<Point<f64>, f64>>::new()),
+                Point { x: 0., y: 0. }
+            ),
+            None
+        );
+
+        let grid = Grid {
+            lower: vec![10., 20.],
+            step: vec![2.5, 4.],
+            shape: vec![7, 5],
+        };
+        assert_eq!(grid.len(), 35);
+        assert_eq!(grid.coordinates(9), vec![15., 24.]);
+        let values: Vec<Option<f64>> =
+            idw.evaluate_grid(&tree, &grid, |c| Point { x: c[0], y: c[1] });
+        let expected: Vec<Option<f64>> = (0..grid.len())
+            .map(|i| {
+                idw.evaluate(
+                    &tree,
+                    Point {
+                        x: grid.coordinates(i)[0],
+                        y: grid.coordinates(i)[1],
+                    },
+                )
+            })
+            .collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_voxel_grid() {
+        use crate::sample::{voxel_grid, Voxel};
+
+        let mut points: Vec<Point<f64>> = random_points(2000, 39);
+        points.push(Point { x: -0.5, y: -3.5 });
+        let voxels: Vec<Voxel<Point<f64>>> = voxel_grid(&points, 7.5);
+        assert_eq!(
+            voxels.iter().map(|voxel| voxel.count).sum::<usize>(),
+            points.len()
+        );
+
+        let cell = |point: &Point<f64>| {
+            (
+                (point.x / 7.5).floor() as i64,
+                (point.y / 7.5).floor() as i64,
+            )
+        };
+        let mut firsts: Vec<usize> = Vec::new();
+        for voxel in &voxels {
+            let inside: Vec<&Point<f64>> = points
+                .iter()
+                .filter(|point| cell(point) == cell(&voxel.first))
+                .collect();
+            assert_eq!(*inside[0], voxel.first);
+            assert_eq!(inside.len(), voxel.count);
+            let x: f64 = inside.iter().map(|point| point.x).sum::<f64>() / inside.len() as f64;
+            let y: f64 = inside.iter().map(|point| point.y).sum::<f64>() / inside.len() as f64;
+            assert!((voxel.centroid[0] - x).abs() < 1e-9 && (voxel.centroid[1] - y).abs() < 1e-9);
+            firsts.push(
+                points
+                    .iter()
+                    .position(|point| *point == voxel.first)
+                    .unwrap(),
+            );
+        }
+        assert!(firsts.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(voxels.last().unwrap().first, Point { x: -0.5, y: -3.5 });
+
+        // A tree gives the same cells, only found in a different order.
+        let from_tree = voxel_grid(KdNode::build(points.clone()).iter(), 7.5);
+        assert_eq!(from_tree.len(), voxels.len());
+        assert_eq!(
+            from_tree.iter().map(|voxel| voxel.count).sum::<usize>(),
+            points.len()
+        );
+    }
+
+    #[test]
+    fn test_poisson_disk() {
+        use crate::sample::poisson_disk;
+
+        let dis =
+            |a: &Point<f64>, b: &Point<f64>| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+        let points: Vec<Point<f64>> = random_points(3000, 40);
+        for separation in [0.5, 3., 20.] {
+            let kept: Vec<Point<f64>> = poisson_disk(&points, separation);
+            let mut expected: Vec<Point<f64>> = Vec::new();
+            for point in &points {
+                if expected.iter().all(|other| dis(point, other) > separation) {
+                    expected.push(*point);
+                }
+            }
+            assert_eq!(kept, expected);
+        }
+
+        let tree: KdNode<f64> = KdNode::build(points.clone());
+        let kept: Vec<Point<f64>> = poisson_disk(tree.iter(), 5.);
+        for (i, a) in kept.iter().enumerate() {
+            assert!(kept[i + 1..].iter().all(|b| dis(a, b) > 5.));
+        }
+        assert!(points
+            .iter()
+            .all(|point| kept.iter().any(|other| dis(point, other) <= 5.)));
+        assert!(poisson_disk::<f64, Point<f64>>(&[], 1.).is_empty());
+    }
+
+    #[test]
+    fn test_local_geometry() {
+        use crate::cloud::{local_geometry, Geometry};
+        use crate::neighbor::Neighbors;
+
+        let dis = |a: &Point3, b: &Point3| {
+            ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+        };
+        let flat: Vec<Point<f64>> = random_points(600, 41);
+        let noise: Vec<Point<f64>> = random_points(600, 42);
+        let points: Vec<Point3> = flat
+            .iter()
+            .zip(&noise)
+            .map(|(p, n)| Point3 {
+                x: p.x,
+                y: p.y,
+                z: n.x * 0.01,
+            })
+            .collect();
+        let tree = KdNode::build_indexed(points.clone());
+
+        for neighbors in [Neighbors::Nearest(8), Neighbors::Within(9.)] {
+            let geometry: Vec<Geometry> = local_geometry(&tree, neighbors);
+            for (point, found) in points.iter().zip(&geometry) {
+                let mut near: Vec<&Point3> = points.iter().collect();
+                near.sort_by(|a, b| dis(point, a).total_cmp(&dis(point, b)));
+                match neighbors {
+                    Neighbors::Nearest(k) => near.truncate(k),
+                    Neighbors::Within(radius) => near.retain(|other| dis(point, other) <= radius),
+                }
+                assert_eq!(found.neighbors, near.len());
+
+                let n: f64 = near.len() as f64;
+                let centroid: Vec<f64> = (0..3)
+                    .map(|dim| near.iter().map(|p| p.get(dim)).sum::<f64>() / n)
+                    .collect();
+                assert!(found
+                    .centroid
+                    .iter()
+                    .zip(&centroid)
+                    .all(|(a, b)| (a - b).abs() < 1e-9));
+                for row in 0..3 {
+                    for column in 0..3 {
+                        let expected: f64 = near
+                            .iter()
+                            .map(|p| {
+                                (p.get(row) - centroid[row]) * (p.get(column) - centroid[column])
+                            })
+                            .sum::<f64>()
+                            / n;
+                        assert!((found.covariance[row * 3 + column] - expected).abs() < 1e-9);
+                    }
+                }
+
+                // The normal is a unit eigenvector of the smallest eigenvalue.
+                assert!(found.eigenvalues.windows(2).all(|pair| pair[0] <= pair[1]));
+                let trace: f64 = (0..3).map(|dim| found.covariance[dim * 4]).sum();
+                assert!(
+                    (found.eigenvalues.iter().sum::<f64>() - trace).abs() < 1e-9 * trace.max(1.)
+                );
+                assert!((found.normal.iter().map(|v| v * v).sum::<f64>() - 1.).abs() < 1e-9);
+                for row in 0..3 {
+                    let product: f64 = (0..3)
+                        .map(|column| found.covariance[row * 3 + column] * found.normal[column])
+                        .sum();
+                    assert!(
+                        (product - found.eigenvalues[0] * found.normal[row]).abs()
+                            < 1e-9 * trace.max(1.)
+                    );
+                }
+                let curvature: f64 = if trace > 0. {
+                    found.eigenvalues[0] / trace
+                } else {
+                    0.
+                };
+                assert!((found.curvature - curvature).abs() < 1e-9);
+            }
+        }
+
+        // Points close to the plane z = 0 have normals close to the z axis.
+        let geometry: Vec<Geometry> = local_geometry(&tree, Neighbors::Nearest(20));
+        assert!(geometry
+            .iter()
+            .all(|found| found.normal[2].abs() > 0.99 && found.curvature < 0.01));
+
+        // A single point has no spread.
+        let single = local_geometry(
+            &KdNode::build_indexed(vec![Point3 {
+                x: 1.,
+                y: 2.,
+                z: 3.,
+            }]),
+            Neighbors::Nearest(4),
+        );
+        assert_eq!(
+            (
+                single[0].neighbors,
+                single[0].curvature,
+                single[0].eigenvalues.clone()
+            ),
+            (1, 0., vec![0., 0., 0.])
+        );
+        assert_eq!(single[0].centroid, vec![1., 2., 3.]);
+    }
+
+    #[test]
+    fn test_icp() {
+        use crate::cloud::{icp, Icp, Registration, Transform};
+
+        // A cloud centered on the origin, moved by a small rotation about every axis and a shift.
+        let target: Vec<Point3> = random_points(800, 43)
+            .iter()
+            .zip(random_points(800, 44))
+            .map(|(a, b)| Point3 {
+                x: a.x - 50.,
+                y: a.y - 50.,
+                z: b.x - 50.,
+            })
+            .collect();
+        let (a, b, c) = (0.02_f64, -0.015_f64, 0.01_f64);
+        let rotation: Vec<f64> = vec![
+            b.cos() * c.cos(),
+            a.sin() * b.sin() * c.cos() - a.cos() * c.sin(),
+            a.cos() * b.sin() * c.cos() + a.sin() * c.sin(),
+            b.cos() * c.sin(),
+            a.sin() * b.sin() * c.sin() + a.cos() * c.cos(),
+            a.cos() * b.sin() * c.sin() - a.sin() * c.cos(),
+            -b.sin(),
+            a.sin() * b.cos(),
+            a.cos() * b.cos(),
+        ];
+        let moved = Transform {
+            rotation,
+            translation: vec![0.4, -0.3, 0.2],
+        };
+        let source: Vec<Point3> = target
+            .iter()
+            .map(|p| {
+                let c: Vec<f64> = moved.apply(&[p.x, p.y, p.z]);
+                Point3 {
+                    x: c[0],
+                    y: c[1],
+                    z: c[2],
+                }
+            })
+            .collect();
+        let to_point = |c: &[f64]| Point3 {
+            x: c[0],
+            y: c[1],
+            z: c[2],
+        };
+
+        let tree: KdNode<f64, Point3> = KdNode::build(target.clone());
+        let registration: Registration = icp(&source, &tree, Icp::default(), to_point);
+        assert!(registration.converged);
+        assert!(registration.rmse < 1e-6);
+        assert_eq!(registration.correspondences, 800);
+        // The transform found undoes the one applied.
+        for (moved, original) in source.iter().zip(&target) {
+            let back: Vec<f64> = registration.transform.apply(&[moved.x, moved.y, moved.z]);
+            assert!(
+                (back[0] - original.x).abs() < 1e-6
+                    && (back[1] - original.y).abs() < 1e-6
+                    && (back[2] - original.z).abs() < 1e-6
+            );
+        }
+        let rotation: &[f64] = &registration.transform.rotation;
+        for i in 0..3 {
+            for j in 0..3 {
+                let dot: f64 = (0..3)
+                    .map(|k| rotation[i * 3 + k] * rotation[j * 3 + k])
+                    .sum();
+                assert!((dot - if i == j { 1. } else { 0. }).abs() < 1e-9);
+            }
+        }
+
+        let once: Registration = icp(
+            &source,
+            &tree,
+            Icp {
+                max_iterations: 1,
+                ..Icp::default()
+            },
+            to_point,
+        );
+        assert_eq!((once.iterations, once.converged), (1, false));
+        assert!(once.rmse < registration.rmse.max(1.));
+        let none: Registration = icp(
+            &source,
+            &tree,
+            Icp {
+                max_distance: 1e-3,
+                ..Icp::default()
+            },
+            to_point,
+        );
+        assert_eq!(
+            (
+                none.iterations,
+                none.correspondences,
+                none.converged,
+                none.rmse
+            ),
+            (0, 0, false, f64::INFINITY)
+        );
+        assert_eq!(none.transform, Transform::identity(3));
+
+        // In two dimensions, with far away points left out by the maximum distance.
+        let target: Vec<Point<f64>> = random_points(500, 45);
+        let (sin, cos) = 0.03_f64.sin_cos();
+        let mut source: Vec<Point<f64>> = target
+            .iter()
+            .map(|p| Point {
+                x: cos * (p.x - 50.) - sin * (p.y - 50.) + 50.3,
+                y: sin * (p.x - 50.) + cos * (p.y - 50.) + 49.8,
+            })
+            .collect();
+        source.extend([Point { x: 500., y: 500. }, Point { x: -300., y: 20. }]);
+        let settings = Icp {
+            max_distance: 5.,
+            tolerance: 1e-10,
+            ..Icp::default()
+        };
+        let registration: Registration =
+            icp(&source, &KdNode::build(target.clone()), settings, |c| {
+                Point { x: c[0], y: c[1] }
+            });
+        assert!(registration.converged && registration.rmse < 1e-6);
+        assert_eq!(registration.correspondences, 500);
+        let back: Vec<f64> = registration.transform.apply(&[source[7].x, source[7].y]);
+        assert!((back[0] - target[7].x).abs() < 1e-6 && (back[1] - target[7].y).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,117 @@
+use crate::point::KdPoint;
+use crate::{KdNode, Node, KDT};
+
+/// A step of a walk, the region is narrowed before a child is searched and put back once it has been.
+enum Step<'a, T: KDT, P: KdPoint<Scalar = T>> {
+    /// Search the node, the region around it has already been narrowed
+    Visit(&'a KdNode<T, P>),
+    /// Narrow the region along `dim` before searching a child
+    Narrow(usize, f64, f64),
+    /// Put the region back along `dim` once the child has been searched
+    Restore(usize, f64, f64),
+}
+
+impl<T: KDT, P: KdPoint<Scalar = T>> KdNode<T, P> {
+    /// Walk the tree depth first, keeping track of the region every node lies in
+    ///
+    /// Every node splits the space around it in two. The left child holds the points at or above
+    /// the split, the right child at or below, so a child lies in the part of its parent's region
+    /// on its side of the split. The region is given as its `lower` and `upper` bound along every
+    /// axis, either of which may be infinite. Nodes that store a bounding box are given their box
+    /// instead.
+    ///
+    /// `prune` is given the region of every node the walk reaches and skips the node along with
+    /// its subtree by returning `true`. Otherwise `visit` is given the node, its point and its
+    /// region, and returns whether to carry on into the node's children. Both are handed `state`,
+    /// which only `visit` may change. `left_first` is given the axis and value of a split and
+    /// returns whether to search the left child before the right one.
+    pub(crate) fn walk<S>(
+        &self,
+        state: &mut S,
+        left_first: impl Fn(usize, f64) -> bool,
+        mut prune: impl FnMut(&S, &[f64], &[f64]) -> bool,
+        mut visit: impl FnMut(&mut S, &KdNode<T, P>, &P, &[f64], &[f64]) -> bool,
+    ) {
+        let mut lower: Vec<f64> = vec![f64::NEG_INFINITY; P::DIMENSIONS];
+        let mut upper: Vec<f64> = vec![f64::INFINITY; P::DIMENSIONS];
+        let mut stack: Vec<Step<T, P>> = vec![Step::Visit(self)];
+        while let Some(step) = stack.pop() {
+            let node: &KdNode<T, P> = match step {
+                Step::Visit(node) => node,
+                Step::Narrow(dim, min, max) | Step::Restore(dim, min, max) => {
+                    lower[dim] = min;
+                    upper[dim] = max;
+                    continue;
+                }
+            };
+
+            if let Node {
+                point,
+                dim,
+                left,
+                right,
+                ..
+            } = node
+            {
+                let (region_lower, region_upper) = node_region(node, &lower, &upper);
+                if prune(state, region_lower, region_upper)
+                    || !visit(state, node, point, region_lower, region_upper)
+                {
+                    continue;
+                }
+
+                let dim: usize = dim.index();
+                let split: f64 = point.get(dim).into();
+                let mut children = [(left, split, upper[dim]), (right, lower[dim], split)];
+
+                // The child searched first is pushed last.
+                if left_first(dim, split) {
+                    children.swap(0, 1);
+                }
+                for (child, min, max) in children {
+                    if !child.is_empty() {
+                        stack.push(Step::Restore(dim, lower[dim], upper[dim]));
+                        stack.push(Step::Visit(child));
+                        stack.push(Step::Narrow(dim, min, max));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The region a node lies in, its bounding box when it stores one or else `lower` and `upper`
+fn node_region<'r, T: KDT, P: KdPoint<Scalar = T>>(
+    node: &'r KdNode<T, P>,
+    lower: &'r [f64],
+    upper: &'r [f64],
+) -> (&'r [f64], &'r [f64]) {
+    match node.bounds() {
+        Some(bounds) => (bounds.lower(), bounds.upper()),
+        None => (lower, upper),
+    }
+}
+
+/// The smallest distance from the origin to a point in the region between `lower` and `upper`
+pub(crate) fn min_distance<P: KdPoint>(origin: &P, lower: &[f64], upper: &[f64]) -> f64 {
+    (0..P::DIMENSIONS)
+        .map(|dim| {
+            let value: f64 = origin.get(dim).into();
+            let gap: f64 = (lower[dim] - value).max(value - upper[dim]).max(0.);
+            gap * gap
+        })
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// The largest distance from the origin to a point in the region between `lower` and `upper`
+pub(crate) fn max_distance<P: KdPoint>(origin: &P, lower: &[f64], upper: &[f64]) -> f64 {
+    (0..P::DIMENSIONS)
+        .map(|dim| {
+            let value: f64 = origin.get(dim).into();
+            let reach: f64 = (value - lower[dim]).abs().max((upper[dim] - value).abs());
+            reach * reach
+        })
+        .sum::<f64>()
+        .sqrt()
+}